@@ -190,7 +190,7 @@ const getModels = async () => {
         });
     } catch (err) {
         const attempts = status.attempts + 1;
-        setStatus({ ...noData, msg: err.toString(), attempts });
+        setStatus({ ...noData, msg: err.message || String(err), attempts });
         setTimeout(() => getModels(), config.server.retry);
     }
 };
@@ -1,16 +1,111 @@
-use crate::error::{Error, Result};
+use crate::error::{Error, ErrorCode, Result};
+use crate::expression;
 use crate::json_schema;
 use crate::sql;
 use crate::utils;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Visibility {
     User,
     Global,
     Tenant,
 }
 
+/// Largest precision of decimal attributes, as supported by most SQL engines.
+pub const MAX_DECIMAL_PRECISION: u32 = 38;
+
+/// Strategy generating the `id` of rows, and thus the type of foreign keys.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum Identity {
+    /// Integers assigned by the database through AUTOINCREMENT.
+    #[default]
+    Integer,
+    /// Lowercase hyphenated UUID text, generated as a random (version 4)
+    /// UUID by the database when not provided.
+    Uuid,
+    /// ULID text in Crockford's base32, which must be provided on insert.
+    Ulid,
+}
+
+/// Random version 4 UUID in SQLite, which has no builtin for it.
+const SQL_UUID_V4: &str = "lower(hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' || substr(hex(randomblob(2)), 2) || '-' || substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' || hex(randomblob(6)))";
+
+impl Identity {
+    pub fn as_sql_type(&self) -> sql::Type {
+        match self {
+            Identity::Integer => sql::Type::Integer,
+            Identity::Uuid | Identity::Ulid => sql::Type::Text,
+        }
+    }
+
+    /// Primary key column of base tables.
+    pub fn as_sql_column(&self) -> sql::Column {
+        let column = sql::Column {
+            name: sql::BASE_ID.to_owned(),
+            ty: self.as_sql_type(),
+            primary_key: true,
+            ..Default::default()
+        };
+        match self {
+            Identity::Integer => sql::Column {
+                auto_increment: true,
+                ..column
+            },
+            Identity::Uuid => sql::Column {
+                default: Some(format!("({})", SQL_UUID_V4)),
+                not_null: true,
+                ..column
+            },
+            Identity::Ulid => sql::Column {
+                not_null: true,
+                ..column
+            },
+        }
+    }
+
+    /// Type of ids, and of foreign keys referencing them, in JSON Schema.
+    pub fn as_json_schema_type(&self) -> json_schema::Type {
+        match self {
+            Identity::Integer => json_schema::Type::new_from_str("integer"),
+            Identity::Uuid => json_schema::Type::new_from_str("string").with_pattern(Some(
+                "^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$".to_owned(),
+            )),
+            Identity::Ulid => json_schema::Type::new_from_str("string")
+                .with_pattern(Some("^[0-9A-HJKMNP-TV-Z]{26}$".to_owned())),
+        }
+    }
+}
+
+/// Wire representation of timestamps of an entity or relation.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum TimestampFormat {
+    /// RFC 3339 strings in UTC, e.g. `2020-06-23T11:59:45Z`, both for
+    /// `timestamp` attributes and the base `created_at`/`updated_at` columns.
+    Rfc3339,
+    /// Compatibility mode, the default so that specs predating `date`/`time`
+    /// keep working: `timestamp` attributes are integers in JSON Schema and
+    /// the base columns are filled with SQLite's `CURRENT_TIMESTAMP`.
+    #[default]
+    Legacy,
+}
+
+impl From<TimestampFormat> for sql::Clock {
+    fn from(format: TimestampFormat) -> Self {
+        match format {
+            TimestampFormat::Rfc3339 => sql::Clock::Rfc3339,
+            TimestampFormat::Legacy => sql::Clock::CurrentTimestamp,
+        }
+    }
+}
+
+/// Type of an attribute.
+///
+/// Plain types are written as strings (`"string"`), while types carrying
+/// parameters are written as an object keyed by the type name, e.g.
+/// `{"enum": ["new", "shipped"]}`.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum AttributeType {
     #[serde(rename = "string")]
@@ -21,8 +116,51 @@ pub enum AttributeType {
     Real,
     #[serde(rename = "bool")]
     Bool,
+    /// Calendar date, `YYYY-MM-DD`.
+    #[serde(rename = "date")]
+    Date,
+    /// Time of day, `HH:MM:SS` with optional fractional seconds.
+    #[serde(rename = "time")]
+    Time,
+    /// Point in time, represented according to the `TimestampFormat`.
     #[serde(rename = "timestamp")]
     Timestamp,
+    /// Elapsed time as an integer number of seconds.
+    #[serde(rename = "duration")]
+    Duration,
+    /// Exact decimal number with at most `precision` digits, `scale` of them
+    /// after the decimal point, e.g. `{"decimal": {"precision": 10, "scale": 2}}`.
+    ///
+    /// Values are exchanged as strings such as `"1234.50"` and stored as
+    /// TEXT, since SQLite's NUMERIC affinity would round them to REAL.
+    #[serde(rename = "decimal")]
+    Decimal { precision: u32, scale: u32 },
+    /// Any JSON value, optionally described by a sub-schema which is
+    /// inlined in the generated schemas, e.g. `{"json": {"type": "object"}}`.
+    /// `{"json": {}}` accepts any value.
+    ///
+    /// Values are stored as JSON text checked with `json_valid()`. Defaults
+    /// are only allowed without a sub-schema, which they could not be
+    /// checked against.
+    #[serde(rename = "json")]
+    Json(Map<String, Value>),
+    /// Text restricted to a fixed set of values.
+    #[serde(rename = "enum")]
+    Enum(Vec<String>),
+    /// Binary content such as a file, optionally limited to `max_size` bytes
+    /// and to some media types, e.g.
+    /// `{"attachment": {"max_size": 1048576, "media_types": ["application/pdf"]}}`.
+    ///
+    /// Values are exchanged as base64 strings and stored as BLOB. Media types
+    /// are advisory: neither the generated schemas nor the database enforce
+    /// them, a single one being reported as `contentMediaType`.
+    #[serde(rename = "attachment")]
+    Attachment {
+        #[serde(default)]
+        max_size: Option<u64>,
+        #[serde(default)]
+        media_types: Vec<String>,
+    },
 }
 
 impl Default for AttributeType {
@@ -31,6 +169,21 @@ impl Default for AttributeType {
     }
 }
 
+impl AttributeType {
+    /// Whether `minimum`/`maximum` apply to values of this type.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            AttributeType::Integer | AttributeType::Real | AttributeType::Duration
+        )
+    }
+
+    /// Whether `min_length`/`max_length`/`pattern` apply to values of this type.
+    pub fn is_text(&self) -> bool {
+        matches!(self, AttributeType::String)
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Attribute {
     pub id: String,
@@ -43,62 +196,549 @@ pub struct Attribute {
     pub unique: bool,
     #[serde(rename = "type")]
     pub ty: AttributeType,
+    /// Inclusive lower bound of numeric values.
+    #[serde(default)]
+    pub minimum: Option<f64>,
+    /// Inclusive upper bound of numeric values.
+    #[serde(default)]
+    pub maximum: Option<f64>,
+    /// Minimum number of characters of text values.
+    #[serde(default)]
+    pub min_length: Option<u32>,
+    /// Maximum number of characters of text values.
+    #[serde(default)]
+    pub max_length: Option<u32>,
+    /// Regular expression text values must match.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Value used when none is provided, must match the attribute type.
+    #[serde(default)]
+    pub default: Option<Value>,
+    /// Whether the attribute holds an array of values of its type.
+    /// Constraints apply to each item, the array is stored as JSON text.
+    #[serde(default)]
+    pub list: bool,
+    /// Expression over sibling attributes computing the value, e.g.
+    /// `unit_price * quantity`. Computed values are read-only.
+    #[serde(default)]
+    pub expression: Option<String>,
 }
 
 impl Attribute {
     pub fn as_sql_type(&self) -> sql::Type {
-        match self.ty {
+        if self.list {
+            return sql::Type::Text;
+        }
+
+        match &self.ty {
             AttributeType::String => sql::Type::Text,
             AttributeType::Integer => sql::Type::Integer,
             AttributeType::Real => sql::Type::Real,
             AttributeType::Bool => sql::Type::Boolean,
-            AttributeType::Timestamp => sql::Type::Date,
+            AttributeType::Date => sql::Type::Date,
+            AttributeType::Time => sql::Type::Time,
+            AttributeType::Timestamp => sql::Type::Timestamp,
+            AttributeType::Duration => sql::Type::Integer,
+            AttributeType::Decimal { .. } => sql::Type::Text,
+            AttributeType::Json(_) => sql::Type::Text,
+            AttributeType::Enum(_) => sql::Type::Text,
+            AttributeType::Attachment { .. } => sql::Type::Blob,
         }
     }
 
-    pub fn as_json_schema_type(&self) -> json_schema::Type {
-        let as_str = match self.ty {
-            AttributeType::String => "string",
-            AttributeType::Integer => "integer",
-            AttributeType::Real => "number",
-            AttributeType::Bool => "boolean",
-            AttributeType::Timestamp => "integer",
+    /// Expressions of the CHECK constraints of the attribute's column.
+    ///
+    /// `pattern` has no counterpart here since SQLite does not ship a
+    /// REGEXP implementation, it is only enforced through the JSON Schema.
+    /// Neither do the item constraints of list attributes.
+    pub fn as_sql_checks(&self) -> Vec<String> {
+        let mut checks = Vec::new();
+
+        if self.list {
+            let is_array = format!(
+                "json_valid({}) AND json_type({}) = 'array'",
+                self.id, self.id
+            );
+            checks.push(match self.required {
+                true => is_array,
+                false => format!("{} IS NULL OR ({})", self.id, is_array),
+            });
+            return checks;
+        }
+
+        if let AttributeType::Json(_) = &self.ty {
+            checks.push(match self.required {
+                true => format!("json_valid({})", self.id),
+                false => format!("{} IS NULL OR json_valid({})", self.id, self.id),
+            });
+        }
+        if let AttributeType::Decimal { precision, scale } = &self.ty {
+            checks.extend(utils::decimal_check(&self.id, *precision, *scale));
+        }
+        if let AttributeType::Enum(values) = &self.ty {
+            checks.push(format!(
+                "{} IN ({})",
+                self.id,
+                values
+                    .iter()
+                    .map(|v| sql::quote(v))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        if let AttributeType::Attachment {
+            max_size: Some(max_size),
+            ..
+        } = &self.ty
+        {
+            checks.push(format!("length({}) <= {}", self.id, max_size));
+        }
+        if let Some(minimum) = self.minimum {
+            checks.push(format!("{} >= {}", self.id, minimum));
+        }
+        if let Some(maximum) = self.maximum {
+            checks.push(format!("{} <= {}", self.id, maximum));
+        }
+        if let Some(min_length) = self.min_length {
+            checks.push(format!("length({}) >= {}", self.id, min_length));
+        }
+        if let Some(max_length) = self.max_length {
+            checks.push(format!("length({}) <= {}", self.id, max_length));
+        }
+
+        checks
+    }
+
+    /// Default value rendered as an SQL literal.
+    pub fn as_sql_default(&self) -> Option<String> {
+        self.default.as_ref().map(|value| match value {
+            json if self.list || matches!(self.ty, AttributeType::Json(_)) => {
+                sql::quote(&json.to_string())
+            }
+            Value::String(text) => sql::quote(text),
+            Value::Bool(true) => "TRUE".to_owned(),
+            Value::Bool(false) => "FALSE".to_owned(),
+            other => other.to_string(),
+        })
+    }
+
+    pub fn as_json_schema_type(&self, timestamps: TimestampFormat) -> json_schema::Type {
+        let item = self.as_json_schema_item_type(timestamps);
+        let ty = match self.list {
+            true => json_schema::Type::new_array(item),
+            false => item,
         };
-        json_schema::Type::new_from_str(as_str)
+        ty.with_default(self.default.clone())
+            .with_read_only(self.is_computed())
+    }
+
+    /// Whether the value is computed from an expression.
+    pub fn is_computed(&self) -> bool {
+        self.expression.is_some()
+    }
+
+    /// Whether the value must be provided when writing, computed values
+    /// are required of the database but never of clients.
+    pub fn is_required_input(&self) -> bool {
+        self.required && !self.is_computed()
+    }
+
+    /// Type of the attribute's values within expressions, if it can be used in one.
+    fn as_expression_kind(&self) -> Option<expression::Kind> {
+        if self.list {
+            return None;
+        }
+
+        match self.ty {
+            AttributeType::String | AttributeType::Enum(_) => Some(expression::Kind::Text),
+            AttributeType::Integer | AttributeType::Duration => Some(expression::Kind::Integer),
+            AttributeType::Real => Some(expression::Kind::Real),
+            _ => None,
+        }
+    }
+
+    /// Validates the expression of a computed attribute against its
+    /// siblings, which it may reference unless they are computed too.
+    pub fn validate_expression(&self, siblings: &[Attribute]) -> Result<()> {
+        let text = match &self.expression {
+            Some(text) => text,
+            None => return Ok(()),
+        };
+        let err = |msg: &str| {
+            Error::with_code(
+                ErrorCode::InvalidExpression,
+                &format!("invalid expression of attribute '{}': {}", self.id, msg),
+            )
+            .at("/expression")
+        };
+
+        if self.default.is_some() || self.list {
+            return Err(err(
+                "computed attributes cannot have a default nor be lists",
+            ));
+        }
+
+        let expr = expression::parse(text).map_err(|e| err(e.msg()))?;
+        let column = |id: &str| -> Result<expression::Kind> {
+            let attr = siblings
+                .iter()
+                .find(|attr| attr.id == id)
+                .ok_or_else(|| Error::new(&format!("attribute '{}' not found", id)))?;
+            if attr.is_computed() {
+                return Err(Error::new(&format!("attribute '{}' is computed", id)));
+            }
+            attr.as_expression_kind()
+                .ok_or_else(|| Error::new(&format!("attribute '{}' cannot be used", id)))
+        };
+        let kind = expr.kind(&column).map_err(|e| err(e.msg()))?;
+
+        let fits = match (&self.ty, kind) {
+            (AttributeType::String, expression::Kind::Text) => true,
+            (AttributeType::Integer, expression::Kind::Integer) => true,
+            (AttributeType::Duration, expression::Kind::Integer) => true,
+            (AttributeType::Real, k) => k != expression::Kind::Text,
+            _ => false,
+        };
+        if !fits {
+            return Err(err(&format!(
+                "{:?} result does not match the attribute type",
+                kind
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// JSON Schema type of a single value, i.e. of each item of lists.
+    fn as_json_schema_item_type(&self, timestamps: TimestampFormat) -> json_schema::Type {
+        let legacy = timestamps == TimestampFormat::Legacy;
+        let (as_str, format) = match &self.ty {
+            AttributeType::String => ("string", None),
+            AttributeType::Integer => ("integer", None),
+            AttributeType::Real => ("number", None),
+            AttributeType::Bool => ("boolean", None),
+            AttributeType::Date => ("string", Some("date")),
+            AttributeType::Time => ("string", Some("time")),
+            AttributeType::Timestamp if legacy => ("integer", None),
+            AttributeType::Timestamp => ("string", Some("date-time")),
+            AttributeType::Duration => ("integer", None),
+            AttributeType::Decimal { .. } => ("string", None),
+            AttributeType::Json(schema) => return json_schema::Type::new_inline(schema),
+            AttributeType::Enum(_) => ("string", None),
+            AttributeType::Attachment { .. } => ("string", None),
+        };
+        let ty = json_schema::Type::new_from_str(as_str)
+            .with_format(format)
+            .with_bounds(self.minimum, self.maximum)
+            .with_length(self.min_length, self.max_length)
+            .with_pattern(self.pattern.clone());
+
+        match &self.ty {
+            AttributeType::Decimal { precision, scale } => {
+                ty.with_pattern(utils::decimal_pattern(*precision, *scale))
+            }
+            AttributeType::Enum(values) => {
+                ty.with_enum(values.iter().map(|v| Value::String(v.clone())).collect())
+            }
+            AttributeType::Attachment {
+                max_size,
+                media_types,
+            } => {
+                let max_length = max_size.map(|size| utils::base64_length(size) as u32);
+                ty.with_length(None, max_length)
+                    .with_content("base64", media_types)
+            }
+            _ => ty,
+        }
     }
 
     /// Validates that the rules
-    pub fn validate(&self) -> Result<()> {
+    pub fn validate(&self, timestamps: TimestampFormat) -> Result<()> {
         match self.id.as_str() {
             "id" | "created_at" | "updated_at" | "rev" => {
-                return Err(Error::new(&format!(
-                    "attribute id '{}' cannot be used",
-                    self.id
-                )));
+                return Err(Error::with_code(
+                    ErrorCode::InvalidAttribute,
+                    &format!("attribute id '{}' cannot be used", self.id),
+                )
+                .at("/id"));
             }
             _ => {}
         }
 
+        if self.list && self.unique {
+            return Err(Error::with_code(
+                ErrorCode::InvalidAttribute,
+                &format!("list attribute '{}' cannot be unique", self.id),
+            )
+            .at("/unique"));
+        }
+
         if !utils::is_snake_case(&self.id) {
-            return Err(Error::new(&format!(
+            return Err(Error::with_code(
+                ErrorCode::InvalidAttribute,
                 "attribute id must be snake_case all lowercase",
-            )));
+            )
+            .at("/id"));
+        }
+
+        if let AttributeType::Decimal { precision, scale } = self.ty {
+            if precision == 0 || precision > MAX_DECIMAL_PRECISION {
+                return Err(Error::with_code(
+                    ErrorCode::InvalidAttribute,
+                    &format!(
+                        "decimal attribute '{}' precision must be between 1 and {}",
+                        self.id, MAX_DECIMAL_PRECISION
+                    ),
+                )
+                .at("/type"));
+            }
+
+            if scale > precision {
+                return Err(Error::with_code(
+                    ErrorCode::InvalidAttribute,
+                    &format!(
+                        "decimal attribute '{}' scale cannot exceed its precision",
+                        self.id
+                    ),
+                )
+                .at("/type"));
+            }
+        }
+
+        if let AttributeType::Json(schema) = &self.ty {
+            let is_type_name = |value: &Value| {
+                matches!(
+                    value.as_str(),
+                    Some("null" | "boolean" | "object" | "array" | "number" | "integer" | "string")
+                )
+            };
+            let type_ok = match schema.get("type") {
+                None => true,
+                Some(Value::Array(names)) => names.iter().all(is_type_name),
+                Some(name) => is_type_name(name),
+            };
+            if !type_ok {
+                return Err(Error::with_code(
+                    ErrorCode::InvalidAttribute,
+                    &format!(
+                        "json attribute '{}' sub-schema has an invalid 'type'",
+                        self.id
+                    ),
+                )
+                .at("/type"));
+            }
+        }
+
+        if let AttributeType::Enum(values) = &self.ty {
+            if values.is_empty() {
+                return Err(Error::with_code(
+                    ErrorCode::InvalidAttribute,
+                    &format!("enum attribute '{}' must list at least one value", self.id),
+                )
+                .at("/type"));
+            }
+
+            let mut seen: BTreeSet<&str> = BTreeSet::new();
+            if let Some(value) = values.iter().find(|v| !seen.insert(v.as_str())) {
+                return Err(Error::with_code(
+                    ErrorCode::InvalidAttribute,
+                    &format!(
+                        "enum attribute '{}' has repeated value '{}'",
+                        self.id, value
+                    ),
+                )
+                .at("/type"));
+            }
+        }
+
+        if let AttributeType::Attachment {
+            max_size,
+            media_types,
+        } = &self.ty
+        {
+            let err = |msg: &str| -> Result<()> {
+                Err(Error::with_code(
+                    ErrorCode::InvalidAttribute,
+                    &format!("attachment attribute '{}' {}", self.id, msg),
+                )
+                .at("/type"))
+            };
+            if *max_size == Some(0) {
+                return err("max_size must be positive");
+            }
+            if max_size.is_some_and(|size| utils::base64_length(size) > u32::MAX as u64) {
+                return err("max_size is too large");
+            }
+            let mut seen: BTreeSet<&str> = BTreeSet::new();
+            for media_type in media_types {
+                if !utils::is_media_type(media_type) {
+                    return err(&format!("has invalid media type '{}'", media_type));
+                }
+                if !seen.insert(media_type.as_str()) {
+                    return err(&format!("has repeated media type '{}'", media_type));
+                }
+            }
+            if self.default.is_some() {
+                return Err(Error::with_code(
+                    ErrorCode::InvalidAttribute,
+                    &format!("attachment attribute '{}' cannot have a default", self.id),
+                )
+                .at("/default"));
+            }
+        }
+
+        self.validate_constraints()?;
+        self.validate_default(timestamps)
+    }
+
+    /// Validates that value constraints fit the attribute type and
+    /// do not contradict each other.
+    fn validate_constraints(&self) -> Result<()> {
+        let err = |field: &str, msg: &str| -> Result<()> {
+            Err(Error::with_code(
+                ErrorCode::InvalidAttribute,
+                &format!("invalid constraints on attribute '{}': {}", self.id, msg),
+            )
+            .at(&format!("/{}", field)))
+        };
+
+        let bound = match self.minimum {
+            Some(_) => "minimum",
+            None => "maximum",
+        };
+        let has_bounds = self.minimum.is_some() || self.maximum.is_some();
+        if has_bounds && !self.ty.is_numeric() {
+            return err(bound, "minimum/maximum only apply to numeric types");
+        }
+
+        let text_field = match (self.min_length, self.max_length) {
+            (Some(_), _) => "min_length",
+            (None, Some(_)) => "max_length",
+            (None, None) => "pattern",
+        };
+        let has_text = self.min_length.is_some()
+            || self.max_length.is_some()
+            || self.pattern.is_some();
+        if has_text && !self.ty.is_text() {
+            return err(
+                text_field,
+                "min_length/max_length/pattern only apply to string types",
+            );
+        }
+
+        if let (Some(minimum), Some(maximum)) = (self.minimum, self.maximum) {
+            if minimum > maximum {
+                return err("minimum", "minimum is greater than maximum");
+            }
+        }
+
+        if let (Some(min_length), Some(max_length)) = (self.min_length, self.max_length) {
+            if min_length > max_length {
+                return err("min_length", "min_length is greater than max_length");
+            }
+        }
+
+        if self.pattern.as_deref() == Some("") {
+            return err("pattern", "pattern cannot be empty");
         }
 
         Ok(())
     }
+
+    /// Validates that the default value matches the attribute type and
+    /// the constraints that can be checked without a regex engine.
+    fn validate_default(&self, timestamps: TimestampFormat) -> Result<()> {
+        let value = match &self.default {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+
+        let sub_schema = matches!(&self.ty, AttributeType::Json(schema) if !schema.is_empty());
+        let problem = match (self.list, value) {
+            // Checking values against a sub-schema needs a JSON Schema validator
+            _ if sub_schema => Some("it cannot be checked against the sub-schema".to_owned()),
+            (true, Value::Array(items)) => items
+                .iter()
+                .find_map(|item| self.value_problem(item, timestamps)),
+            (true, _) => Some(format!("{} is not a list", value)),
+            (false, value) => self.value_problem(value, timestamps),
+        };
+
+        match problem {
+            Some(msg) => Err(Error::with_code(
+                ErrorCode::InvalidAttribute,
+                &format!("invalid default of attribute '{}': {}", self.id, msg),
+            )
+            .at("/default")),
+            None => Ok(()),
+        }
+    }
+
+    /// Describes why a single value does not fit the attribute, if it does not.
+    fn value_problem(&self, value: &Value, timestamps: TimestampFormat) -> Option<String> {
+        let type_matches = match &self.ty {
+            AttributeType::String => value.is_string(),
+            AttributeType::Integer => value.is_i64() || value.is_u64(),
+            AttributeType::Real => value.is_number(),
+            AttributeType::Bool => value.is_boolean(),
+            AttributeType::Date => value.as_str().is_some_and(utils::is_date),
+            AttributeType::Time => value.as_str().is_some_and(utils::is_time),
+            AttributeType::Timestamp if timestamps == TimestampFormat::Legacy => {
+                value.is_i64() || value.is_u64()
+            }
+            AttributeType::Timestamp => value.as_str().is_some_and(utils::is_date_time),
+            AttributeType::Duration => value.is_i64() || value.is_u64(),
+            AttributeType::Decimal { precision, scale } => value
+                .as_str()
+                .is_some_and(|text| utils::is_decimal(text, *precision, *scale)),
+            AttributeType::Json(_) => true,
+            AttributeType::Enum(values) => value
+                .as_str()
+                .is_some_and(|text| values.iter().any(|v| v == text)),
+            AttributeType::Attachment { .. } => false,
+        };
+        if !type_matches {
+            return Some(format!("{} does not match the attribute type", value));
+        }
+
+        if let Some(number) = value.as_f64() {
+            if self.minimum.is_some_and(|minimum| number < minimum)
+                || self.maximum.is_some_and(|maximum| number > maximum)
+            {
+                return Some(format!("{} is out of bounds", value));
+            }
+        }
+
+        if let Some(text) = value.as_str() {
+            let length = text.chars().count() as u32;
+            if self.min_length.is_some_and(|min| length < min)
+                || self.max_length.is_some_and(|max| length > max)
+            {
+                return Some(format!("length of {} is out of bounds", value));
+            }
+        }
+
+        None
+    }
 }
 
 impl From<Attribute> for sql::Column {
     fn from(attr: Attribute) -> Self {
         Self {
             ty: attr.as_sql_type(),
+            checks: attr.as_sql_checks(),
+            default: attr.as_sql_default(),
+            // Expressions are validated beforehand, render them as written otherwise.
+            generated: attr.expression.as_deref().map(|text| {
+                expression::parse(text)
+                    .map(|expr| expr.to_string())
+                    .unwrap_or_else(|_| text.to_owned())
+            }),
             name: attr.id,
             not_null: attr.required,
             auto_increment: false,
             primary_key: false,
             unique: attr.unique,
-            default: None,
         }
     }
 }
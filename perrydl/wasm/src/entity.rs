@@ -1,10 +1,25 @@
-use crate::common::{Attribute, UniqueConstraint, Visibility};
-use crate::error::{Error, Result};
+use crate::common::{Attribute, Identity, TimestampFormat, UniqueConstraint, Visibility};
+use crate::error::{Error, ErrorCode, Result};
 use crate::json_schema::{self, JsonSchema};
 use crate::sql;
 use crate::urn;
 use serde::{Deserialize, Serialize};
 
+/// Column telling apart the entities stored in the table of a single table hierarchy.
+pub const DISCRIMINATOR: &str = "entity_type";
+
+/// Mapping of an entity hierarchy to tables.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum Inheritance {
+    /// Subtypes are stored in the table of the root entity, told apart
+    /// by a discriminator column.
+    #[default]
+    SingleTable,
+    /// Each subtype has a table of its own, keyed by the id of the row
+    /// of its parent.
+    Joined,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Entity {
     pub id: u32,
@@ -17,6 +32,18 @@ pub struct Entity {
     pub attributes: Vec<Attribute>,
     #[serde(default)]
     pub unique_constraints: Vec<UniqueConstraint>,
+    #[serde(default)]
+    pub timestamps: TimestampFormat,
+    /// Overrides the identity strategy of the model.
+    #[serde(default)]
+    pub identity: Option<Identity>,
+    /// Urn of the entity this one extends, inheriting its attributes
+    /// and unique constraints.
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Mapping of the hierarchy, set on its root only.
+    #[serde(default)]
+    pub inheritance: Option<Inheritance>,
 }
 
 impl json_schema::GenerateSchema for Entity {
@@ -26,7 +53,16 @@ impl json_schema::GenerateSchema for Entity {
         let mut schema = JsonSchema::new(&self.urn);
         self.attributes
             .iter()
-            .map(|a| schema.add_property(&a.id, a.as_json_schema_type(), a.required))
+            .enumerate()
+            .map(|(i, a)| {
+                a.validate(self.timestamps).map_err(|e| {
+                    e.with_urn(&self.urn)
+                        .with_attribute(&a.id)
+                        .at(&format!("/attributes/{}", i))
+                })?;
+                let ty = a.as_json_schema_type(self.timestamps);
+                schema.add_property(&a.id, ty, a.is_required_input())
+            })
             .collect::<Result<Vec<()>>>()?;
         Ok(schema)
     }
@@ -34,56 +70,148 @@ impl json_schema::GenerateSchema for Entity {
 
 impl Entity {
     pub fn validate(&self) -> Result<()> {
-        let err = |msg: &str| -> Result<()> {
-            Err(Error::new(&format!(
-                "invalid '{}' entity: {}",
-                self.urn, msg
-            )))
+        Error::first_of(self.errors())
+    }
+
+    /// Every problem of the entity on its own, where `validate` stops at the first.
+    pub fn errors(&self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        let err = |code: ErrorCode, pointer: &str, msg: &str| {
+            Error::with_code(code, &format!("invalid '{}' entity: {}", self.urn, msg))
+                .with_urn(&self.urn)
+                .at(pointer)
         };
 
         if urn::basename(&self.urn) != self.singular {
-            return err("urn basename does not match singular");
+            errors.push(err(
+                ErrorCode::InvalidUrn,
+                "/urn",
+                "urn basename does not match singular",
+            ));
         }
 
         if self.singular == self.plural {
-            return err("singular cannot be equal to plural");
+            errors.push(err(
+                ErrorCode::InvalidEntity,
+                "/plural",
+                "singular cannot be equal to plural",
+            ));
         }
 
         if self.singular.is_empty() || self.plural.is_empty() {
-            return err("singular nor plural can be empty strings");
+            let pointer = match self.singular.is_empty() {
+                true => "/singular",
+                false => "/plural",
+            };
+            errors.push(err(
+                ErrorCode::InvalidEntity,
+                pointer,
+                "singular nor plural can be empty strings",
+            ));
         }
 
-        if self.attributes.len() == 0 {
-            return err("attributes list cannot be empty");
+        // Subtypes may only add unique constraints to inherited attributes
+        if self.extends.is_some() {
+            if self.inheritance.is_some() {
+                errors.push(err(
+                    ErrorCode::InvalidInheritance,
+                    "/inheritance",
+                    "inheritance can only be set on the root of a hierarchy",
+                ));
+            }
+        } else if self.attributes.len() == 0 {
+            errors.push(err(
+                ErrorCode::InvalidEntity,
+                "/attributes",
+                "attributes list cannot be empty",
+            ));
         }
 
-        self.attributes
-            .iter()
-            .map(|attr| attr.validate())
-            .collect::<Result<Vec<()>>>()?;
+        for (i, attr) in self.attributes.iter().enumerate() {
+            let checked = attr
+                .validate(self.timestamps)
+                .and_then(|_| attr.validate_expression(&self.attributes));
+            if let Err(e) = checked {
+                errors.push(
+                    e.with_urn(&self.urn)
+                        .with_attribute(&attr.id)
+                        .at(&format!("/attributes/{}", i)),
+                );
+            }
+        }
 
-        self.unique_constraints
-            .iter()
-            .flat_map(|uc| uc.attributes.iter())
-            .find(|&id| self.attributes.iter().find(|attr| attr.id == *id).is_none())
-            .map(|id| Err(Error::new(&format!("attribute with id '{}' not found", id))))
-            .unwrap_or(Ok(()))?;
+        // Attributes of subtypes are checked by the model, which knows inherited ones
+        if self.extends.is_none() {
+            for (i, uc) in self.unique_constraints.iter().enumerate() {
+                for (j, id) in uc.attributes.iter().enumerate() {
+                    let pointer = format!("/unique_constraints/{}/attributes/{}", i, j);
+                    match self.attributes.iter().find(|attr| attr.id == *id) {
+                        Some(attr) if attr.list => errors.push(err(
+                            ErrorCode::InvalidAttribute,
+                            &pointer,
+                            &format!("list attribute '{}' cannot be unique", id),
+                        )),
+                        Some(_) => {}
+                        None => errors.push(err(
+                            ErrorCode::NotFound,
+                            &pointer,
+                            &format!("attribute with id '{}' not found", id),
+                        )),
+                    }
+                }
+            }
+        }
+
+        errors
+    }
 
-        Ok(())
+    /// Identity strategy of the entity, falling back to integers when
+    /// neither the entity nor its model define one.
+    pub fn identity(&self) -> Identity {
+        self.identity.unwrap_or_default()
     }
 
     pub fn as_sql_table(&self) -> sql::Table {
         // Generate table first
         let name = self.as_sql_table_name();
-        let mut base = sql::Table::new_base(name);
+        let id = self.identity().as_sql_column();
+        let mut base = sql::Table::new_base(name, id, self.timestamps.into());
         self.attributes
             .iter()
             .for_each(|attr| base.columns.push(attr.clone().into()));
         base
     }
 
+    /// Table of a subtype mapped to a table of its own, whose id references
+    /// the row of its parent.
+    pub fn as_sql_subtype_table(&self, parent: &Entity) -> sql::Table {
+        let mut table = sql::Table {
+            name: self.as_sql_table_name().to_owned(),
+            columns: vec![sql::Column {
+                name: sql::BASE_ID.to_owned(),
+                ty: self.identity().as_sql_type(),
+                primary_key: true,
+                not_null: true,
+                ..Default::default()
+            }],
+            primary_key: vec![],
+            foreign_keys: vec![sql::ForeignKey {
+                key: sql::BASE_ID.to_owned(),
+                table_name: parent.as_sql_table_name().to_owned(),
+                table_key: sql::BASE_ID.to_owned(),
+                on_delete: Some(sql::ReferentialAction::Cascade),
+                on_update: None,
+            }],
+            unique_constraints: vec![],
+        };
+        self.attributes
+            .iter()
+            .for_each(|attr| table.columns.push(attr.clone().into()));
+        table
+    }
+
     pub fn as_sql_trigger(&self) -> sql::Trigger {
-        sql::Trigger::new_update_trigger(&self.plural)
+        sql::Trigger::new_update_trigger(&self.plural, self.timestamps.into())
     }
 
     pub fn as_sql_table_name(&self) -> &str {
@@ -240,6 +368,385 @@ mod tests {
         }
     }
 
+    #[test]
+    fn entity_enum_attribute() {
+        let mut entity = serde_json::from_str::<Entity>(RAW_ENTITY).unwrap();
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "condition",
+                    "name": "Condition",
+                    "type": { "enum": ["new", "used"] }
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.validate().expect("entity with enum should be valid");
+
+        use json_schema::GenerateSchema;
+        let schema = entity.json_schema_with_url("").unwrap();
+        assert_eq!(
+            serde_json::to_value(&schema).unwrap()["properties"]["condition"],
+            serde_json::json!({ "type": ["string", "null"], "enum": ["new", "used", null] })
+        );
+
+        assert_eq!(
+            entity.as_sql_table().to_string(),
+            "CREATE TABLE parts(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, label TEXT UNIQUE NOT NULL, stock INTEGER, condition TEXT CHECK (condition IN ('new', 'used')));"
+        );
+
+        entity.attributes[2].ty = AttributeType::Enum(vec![]);
+        entity.validate().expect_err("enum without values should be invalid");
+
+        entity.attributes[2].ty = AttributeType::Enum(vec!["new".into(), "new".into()]);
+        entity.validate().expect_err("enum with repeated values should be invalid");
+    }
+
+    #[test]
+    fn entity_constrained_attributes() {
+        let mut entity = serde_json::from_str::<Entity>(RAW_ENTITY).unwrap();
+        entity.attributes[0].min_length = Some(3);
+        entity.attributes[0].max_length = Some(12);
+        entity.attributes[0].pattern = Some("^[A-Z0-9-]+$".to_owned());
+        entity.attributes[1].minimum = Some(0.0);
+        entity.validate().expect("entity with constraints should be valid");
+
+        use json_schema::GenerateSchema;
+        let schema = serde_json::to_value(entity.json_schema_with_url("").unwrap()).unwrap();
+        assert_eq!(
+            schema["properties"],
+            serde_json::json!({
+                "label": { "type": "string", "minLength": 3, "maxLength": 12, "pattern": "^[A-Z0-9-]+$" },
+                "stock": { "type": ["integer", "null"], "minimum": 0.0 }
+            })
+        );
+
+        assert_eq!(
+            entity.as_sql_table().to_string(),
+            "CREATE TABLE parts(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, label TEXT UNIQUE NOT NULL CHECK (length(label) >= 3) CHECK (length(label) <= 12), stock INTEGER CHECK (stock >= 0));"
+        );
+
+        entity.attributes[1].maximum = Some(-1.0);
+        entity.validate().expect_err("minimum above maximum should be invalid");
+
+        entity.attributes[1].maximum = None;
+        entity.attributes[1].pattern = Some("^[0-9]+$".to_owned());
+        entity.validate().expect_err("pattern on an integer should be invalid");
+
+        entity.attributes[1].pattern = None;
+        entity.attributes[0].minimum = Some(1.0);
+        entity.validate().expect_err("minimum on a string should be invalid");
+    }
+
+    #[test]
+    fn entity_default_values() {
+        let mut entity = serde_json::from_str::<Entity>(RAW_ENTITY).unwrap();
+        entity.attributes[1].default = Some(serde_json::json!(0));
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "condition",
+                    "name": "Condition",
+                    "type": { "enum": ["new", "used"] },
+                    "default": "new"
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "notes",
+                    "name": "Notes",
+                    "type": "string",
+                    "default": "it's new"
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.validate().expect("entity with defaults should be valid");
+
+        use json_schema::GenerateSchema;
+        let schema = serde_json::to_value(entity.json_schema_with_url("").unwrap()).unwrap();
+        assert_eq!(
+            schema["properties"]["stock"],
+            serde_json::json!({ "type": ["integer", "null"], "default": 0 })
+        );
+        assert_eq!(schema["properties"]["condition"]["default"], "new");
+
+        assert_eq!(
+            entity.as_sql_table().to_string(),
+            "CREATE TABLE parts(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, label TEXT UNIQUE NOT NULL, stock INTEGER DEFAULT 0, condition TEXT DEFAULT 'new' CHECK (condition IN ('new', 'used')), notes TEXT DEFAULT 'it''s new');"
+        );
+
+        entity.attributes[1].default = Some(serde_json::json!("0"));
+        entity.validate().expect_err("string default on integer should be invalid");
+
+        entity.attributes[1].default = Some(serde_json::json!(-1));
+        entity.attributes[1].minimum = Some(0.0);
+        entity.validate().expect_err("default below minimum should be invalid");
+
+        entity.attributes[1].default = None;
+        entity.attributes[2].default = Some(serde_json::json!("broken"));
+        entity.validate().expect_err("default outside enum should be invalid");
+    }
+
+    #[test]
+    fn entity_temporal_attributes() {
+        let mut entity = serde_json::from_str::<Entity>(RAW_ENTITY).unwrap();
+        entity.timestamps = TimestampFormat::Rfc3339;
+        entity.attributes = serde_json::from_str(
+            r#"[
+                { "id": "released_on", "name": "Released On", "type": "date", "default": "2020-01-31" },
+                { "id": "opens_at", "name": "Opens At", "type": "time" },
+                { "id": "checked_at", "name": "Checked At", "type": "timestamp", "required": true },
+                { "id": "lead_time", "name": "Lead Time", "type": "duration", "minimum": 0 }
+            ]"#,
+        )
+        .unwrap();
+        entity.validate().expect("entity with temporal types should be valid");
+
+        use json_schema::GenerateSchema;
+        let schema = serde_json::to_value(entity.json_schema_with_url("").unwrap()).unwrap();
+        assert_eq!(
+            schema["properties"],
+            serde_json::json!({
+                "released_on": { "type": ["string", "null"], "format": "date", "default": "2020-01-31" },
+                "opens_at": { "type": ["string", "null"], "format": "time" },
+                "checked_at": { "type": "string", "format": "date-time" },
+                "lead_time": { "type": ["integer", "null"], "minimum": 0.0 }
+            })
+        );
+
+        assert_eq!(
+            entity.as_sql_table().columns[3..]
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<String>>(),
+            vec![
+                "released_on DATE DEFAULT '2020-01-31'",
+                "opens_at TIME",
+                "checked_at DATETIME NOT NULL",
+                "lead_time INTEGER CHECK (lead_time >= 0)",
+            ]
+        );
+
+        entity.attributes[2].default = Some(serde_json::json!(1592913585000u64));
+        entity
+            .validate()
+            .expect_err("integer timestamp default should be invalid with RFC 3339");
+
+        entity.timestamps = TimestampFormat::Legacy;
+        entity
+            .validate()
+            .expect("integer timestamp default should be valid in legacy mode");
+        let schema = serde_json::to_value(entity.json_schema_with_url("").unwrap()).unwrap();
+        assert_eq!(
+            schema["properties"]["checked_at"],
+            serde_json::json!({ "type": "integer", "default": 1592913585000u64 })
+        );
+
+        entity.attributes[0].default = Some(serde_json::json!("31/01/2020"));
+        entity.validate().expect_err("malformed date default should be invalid");
+    }
+
+    #[test]
+    fn entity_decimal_attribute() {
+        let mut entity = serde_json::from_str::<Entity>(RAW_ENTITY).unwrap();
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "unit_price",
+                    "name": "Unit Price",
+                    "required": true,
+                    "type": { "decimal": { "precision": 10, "scale": 2 } },
+                    "default": "0.00"
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.validate().expect("entity with decimal should be valid");
+
+        use json_schema::GenerateSchema;
+        let schema = serde_json::to_value(entity.json_schema_with_url("").unwrap()).unwrap();
+        assert_eq!(
+            schema["properties"]["unit_price"],
+            serde_json::json!({
+                "type": "string",
+                "pattern": "^-?[0-9]{1,8}(\\.[0-9]{1,2})?$",
+                "default": "0.00"
+            })
+        );
+        assert_eq!(
+            entity.as_sql_table().columns[5].to_string(),
+            format!(
+                "unit_price TEXT DEFAULT '0.00' NOT NULL CHECK ({})",
+                crate::utils::decimal_check("unit_price", 10, 2).unwrap()
+            )
+        );
+
+        entity.attributes[2].default = Some(serde_json::json!("0.001"));
+        entity.validate().expect_err("default exceeding scale should be invalid");
+
+        entity.attributes[2].default = None;
+        entity.attributes[2].ty = AttributeType::Decimal {
+            precision: 2,
+            scale: 3,
+        };
+        entity.validate().expect_err("scale above precision should be invalid");
+        match entity.json_schema_with_url("") {
+            Err(err) => assert_eq!(err.pointer(), "/attributes/2/type"),
+            Ok(_) => panic!("schema of invalid decimal should not be generated"),
+        }
+
+        entity.attributes[2].ty = AttributeType::Decimal {
+            precision: 0,
+            scale: 0,
+        };
+        entity.validate().expect_err("zero precision should be invalid");
+    }
+
+    #[test]
+    fn entity_json_attribute() {
+        let mut entity = serde_json::from_str::<Entity>(RAW_ENTITY).unwrap();
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "dimensions",
+                    "name": "Dimensions",
+                    "type": {
+                        "json": {
+                            "type": "object",
+                            "properties": { "width": { "type": "number" } },
+                            "required": ["width"]
+                        }
+                    }
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "specs",
+                    "name": "Specs",
+                    "required": true,
+                    "type": { "json": {} },
+                    "default": { "rev": "it's 1" }
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.validate().expect("entity with json should be valid");
+
+        use json_schema::GenerateSchema;
+        let schema = serde_json::to_value(entity.json_schema_with_url("").unwrap()).unwrap();
+        assert_eq!(
+            schema["properties"]["dimensions"],
+            serde_json::json!({
+                "type": ["object", "null"],
+                "properties": { "width": { "type": "number" } },
+                "required": ["width"]
+            })
+        );
+        assert_eq!(
+            schema["properties"]["specs"],
+            serde_json::json!({ "default": { "rev": "it's 1" } })
+        );
+
+        let compiled =
+            jsonschema::JSONSchema::compile(&schema, Some(jsonschema::Draft::Draft7)).unwrap();
+        let instance = serde_json::json!({ "label": "a", "specs": [1], "dimensions": { "width": 2 } });
+        assert!(compiled.is_valid(&instance));
+        let instance = serde_json::json!({ "label": "a", "specs": [1], "dimensions": {} });
+        assert!(!compiled.is_valid(&instance));
+
+        let table = entity.as_sql_table();
+        assert_eq!(
+            table.columns[5].to_string(),
+            "dimensions TEXT CHECK (dimensions IS NULL OR json_valid(dimensions))"
+        );
+        assert_eq!(
+            table.columns[6].to_string(),
+            "specs TEXT DEFAULT '{\"rev\":\"it''s 1\"}' NOT NULL CHECK (json_valid(specs))"
+        );
+
+        entity.attributes[2].ty = AttributeType::Json(
+            serde_json::json!({ "type": "map" })
+                .as_object()
+                .unwrap()
+                .clone(),
+        );
+        entity.validate().expect_err("unknown sub-schema type should be invalid");
+
+        entity.attributes[2].ty = AttributeType::Json(
+            serde_json::json!({ "type": "object", "required": ["a"] })
+                .as_object()
+                .unwrap()
+                .clone(),
+        );
+        entity.attributes[3].default = Some(serde_json::json!({ "b": "x" }));
+        entity.attributes[3].ty = entity.attributes[2].ty.clone();
+        let err = entity
+            .validate()
+            .expect_err("default of a json attribute with a sub-schema should be invalid");
+        assert_eq!(err.pointer(), "/attributes/3/default");
+    }
+
+    #[test]
+    fn entity_list_attribute() {
+        let mut entity = serde_json::from_str::<Entity>(RAW_ENTITY).unwrap();
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "tags",
+                    "name": "Tags",
+                    "type": "string",
+                    "list": true,
+                    "max_length": 8,
+                    "default": ["spare"]
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.validate().expect("entity with list should be valid");
+
+        use json_schema::GenerateSchema;
+        let schema = serde_json::to_value(entity.json_schema_with_url("").unwrap()).unwrap();
+        assert_eq!(
+            schema["properties"]["tags"],
+            serde_json::json!({
+                "type": ["array", "null"],
+                "items": { "type": "string", "maxLength": 8 },
+                "default": ["spare"]
+            })
+        );
+        assert_eq!(
+            entity.as_sql_table().columns[5].to_string(),
+            "tags TEXT DEFAULT '[\"spare\"]' CHECK (tags IS NULL OR (json_valid(tags) AND json_type(tags) = 'array'))"
+        );
+
+        entity.attributes[2].default = Some(serde_json::json!(["much too long"]));
+        entity.validate().expect_err("default item out of bounds should be invalid");
+
+        entity.attributes[2].default = Some(serde_json::json!("spare"));
+        entity.validate().expect_err("scalar default on a list should be invalid");
+
+        entity.attributes[2].default = None;
+        entity.attributes[2].unique = true;
+        entity.validate().expect_err("unique list should be invalid");
+
+        entity.attributes[2].unique = false;
+        entity.unique_constraints.push(UniqueConstraint {
+            attributes: vec!["tags".to_owned()],
+            relations: vec![],
+        });
+        let err = entity
+            .validate()
+            .expect_err("list in a unique constraint should be invalid");
+        assert_eq!(err.pointer(), "/unique_constraints/0/attributes/0");
+    }
+
     #[test]
     fn entity_to_sql() {
         let entity = Entity {
@@ -258,6 +765,7 @@ mod tests {
                     required: true,
                     unique: true,
                     ty: AttributeType::String,
+                    ..Default::default()
                 },
                 Attribute {
                     id: "stock".to_owned(),
@@ -268,6 +776,10 @@ mod tests {
                 },
             ],
             unique_constraints: vec![],
+            timestamps: TimestampFormat::Legacy,
+            identity: None,
+            extends: None,
+            inheritance: None,
         };
 
         assert_eq!(
@@ -275,4 +787,168 @@ mod tests {
             "CREATE TABLE parts(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, label TEXT UNIQUE NOT NULL, stock INTEGER);\nCREATE TRIGGER parts_updated_at AFTER UPDATE ON parts WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE parts SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;".to_owned()
         );
     }
+
+    #[test]
+    fn entity_attachment_attribute() {
+        let mut entity = serde_json::from_str::<Entity>(RAW_ENTITY).unwrap();
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "datasheet",
+                    "name": "Datasheet",
+                    "type": {
+                        "attachment": { "max_size": 6, "media_types": ["application/pdf"] }
+                    }
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "photo",
+                    "name": "Photo",
+                    "required": true,
+                    "type": { "attachment": { "media_types": ["image/png", "image/jpeg"] } }
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity
+            .validate()
+            .expect("entity with attachments should be valid");
+
+        use json_schema::GenerateSchema;
+        let schema = serde_json::to_value(entity.json_schema_with_url("").unwrap()).unwrap();
+        assert_eq!(
+            schema["properties"]["datasheet"],
+            serde_json::json!({
+                "type": ["string", "null"],
+                "maxLength": 8,
+                "contentEncoding": "base64",
+                "contentMediaType": "application/pdf"
+            })
+        );
+        assert_eq!(
+            schema["properties"]["photo"],
+            serde_json::json!({
+                "type": "string",
+                "contentEncoding": "base64"
+            })
+        );
+
+        let compiled =
+            jsonschema::JSONSchema::compile(&schema, Some(jsonschema::Draft::Draft7)).unwrap();
+        let instance = serde_json::json!({ "label": "a", "photo": "", "datasheet": "AAECAwQF" });
+        assert!(compiled.is_valid(&instance));
+        let instance =
+            serde_json::json!({ "label": "a", "photo": "", "datasheet": "AAECAwQFBg==" });
+        assert!(!compiled.is_valid(&instance));
+
+        let table = entity.as_sql_table();
+        assert_eq!(
+            table.columns[5].to_string(),
+            "datasheet BLOB CHECK (length(datasheet) <= 6)"
+        );
+        assert_eq!(table.columns[6].to_string(), "photo BLOB NOT NULL");
+
+        entity.attributes[2].ty = AttributeType::Attachment {
+            max_size: None,
+            media_types: vec!["pdf".into()],
+        };
+        entity
+            .validate()
+            .expect_err("malformed media type should be invalid");
+
+        entity.attributes[2].ty = AttributeType::Attachment {
+            max_size: Some(0),
+            media_types: vec![],
+        };
+        entity
+            .validate()
+            .expect_err("empty max_size should be invalid");
+    }
+
+    #[test]
+    fn entity_computed_attribute() {
+        let mut entity = serde_json::from_str::<Entity>(RAW_ENTITY).unwrap();
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "unit_price",
+                    "name": "Unit Price",
+                    "type": "real"
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "stock_value",
+                    "name": "Stock Value",
+                    "required": true,
+                    "type": "real",
+                    "expression": "unit_price*stock"
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity.attributes.push(
+            serde_json::from_str(
+                r#"{
+                    "id": "title",
+                    "name": "Title",
+                    "type": "string",
+                    "expression": "upper(label) || ' (' || stock || ')'"
+                }"#,
+            )
+            .unwrap(),
+        );
+        entity
+            .validate()
+            .expect("entity with computed attributes should be valid");
+
+        use json_schema::GenerateSchema;
+        let schema = serde_json::to_value(entity.json_schema_with_url("").unwrap()).unwrap();
+        assert_eq!(
+            schema["properties"]["stock_value"],
+            serde_json::json!({ "type": ["number", "null"], "readOnly": true })
+        );
+        assert_eq!(schema["required"], serde_json::json!(["label"]));
+
+        let table = entity.as_sql_table();
+        assert_eq!(
+            table.columns[6].to_string(),
+            "stock_value REAL GENERATED ALWAYS AS (unit_price * stock) NOT NULL"
+        );
+        assert_eq!(
+            table.columns[7].to_string(),
+            "title TEXT GENERATED ALWAYS AS (((upper(label) || ' (') || stock) || ')')"
+        );
+
+        let mut invalid = entity.clone();
+        invalid.attributes[3].expression = Some("label * 2".into());
+        invalid
+            .validate()
+            .expect_err("arithmetic on text should be invalid");
+
+        let mut invalid = entity.clone();
+        invalid.attributes[3].expression = Some("stock_value + 1".into());
+        invalid
+            .validate()
+            .expect_err("referencing computed attributes should be invalid");
+
+        let mut invalid = entity.clone();
+        invalid.attributes[2].expression = Some("label".into());
+        invalid
+            .validate()
+            .expect_err("text result for a real attribute should be invalid");
+
+        let mut invalid = entity;
+        invalid.attributes[3].expression = Some("price * stock".into());
+        invalid
+            .validate()
+            .expect_err("unknown attributes should be invalid");
+    }
 }
@@ -1,14 +1,132 @@
-#[derive(Debug)]
+use serde::{Deserialize, Serialize};
+
+/// Kind of an error, for callers to react to without parsing messages.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum ErrorCode {
+    /// The payload could not be serialized or deserialized.
+    Serialization,
+    /// A urn has invalid characters or does not match its names.
+    InvalidUrn,
+    /// An attribute is inconsistent, e.g. its default does not fit its type.
+    InvalidAttribute,
+    /// The expression of a computed attribute does not parse or type check.
+    InvalidExpression,
+    /// An entity is inconsistent on its own.
+    InvalidEntity,
+    /// A relation is inconsistent on its own.
+    InvalidRelation,
+    /// Entities extend each other inconsistently.
+    InvalidInheritance,
+    /// An entity, relation or attribute referred to does not exist.
+    NotFound,
+    /// Names, tables or columns collide.
+    Conflict,
+    /// A declaration has no effect, e.g. a constraint implied by another.
+    Redundant,
+    /// A generated name is reserved in a target, e.g. an SQL keyword.
+    Reserved,
+    /// An advisory rule of the linter is not followed.
+    Lint,
+    /// Any other inconsistency.
+    Invalid,
+}
+
+#[derive(Debug, Serialize)]
 pub struct Error {
+    code: ErrorCode,
+    #[serde(rename = "message")]
     msg: String,
+    /// JSON pointer to the offending value in the submitted spec,
+    /// empty for the whole spec.
+    pointer: String,
+    /// Boxed to keep results small, most errors having few details.
+    #[serde(flatten)]
+    details: Box<Details>,
+}
+
+/// Optional details of an error.
+#[derive(Debug, Default, Serialize)]
+struct Details {
+    /// Urn of the offending entity or relation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    urn: Option<String>,
+    /// Id of the offending attribute.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attribute: Option<String>,
+    /// Value that would not have the problem, e.g. a safe name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<String>,
 }
 
 impl Error {
     pub fn new(msg: &str) -> Error {
+        Error::with_code(ErrorCode::Invalid, msg)
+    }
+
+    pub fn with_code(code: ErrorCode, msg: &str) -> Error {
         Error {
+            code,
             msg: msg.to_owned(),
+            pointer: String::new(),
+            details: Box::default(),
         }
     }
+
+    /// Set the offending entity or relation, unless already known.
+    pub fn with_urn(mut self, urn: &str) -> Error {
+        self.details.urn.get_or_insert_with(|| urn.to_owned());
+        self
+    }
+
+    /// Set the offending attribute, unless already known.
+    pub fn with_attribute(mut self, id: &str) -> Error {
+        self.details.attribute.get_or_insert_with(|| id.to_owned());
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: &str) -> Error {
+        self.details.suggestion = Some(suggestion.to_owned());
+        self
+    }
+
+    /// Prefix the pointer with the path to the value the error was found in.
+    pub fn at(mut self, path: &str) -> Error {
+        self.pointer.insert_str(0, path);
+        self
+    }
+
+    /// Fail with the first of the errors found, if any.
+    pub fn first_of(errors: Vec<Error>) -> Result<()> {
+        match errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// Message of the error, without prefix.
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    pub fn urn(&self) -> Option<&str> {
+        self.details.urn.as_deref()
+    }
+
+    pub fn attribute(&self) -> Option<&str> {
+        self.details.attribute.as_deref()
+    }
+
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    pub fn suggestion(&self) -> Option<&str> {
+        self.details.suggestion.as_deref()
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -19,10 +137,62 @@ impl std::fmt::Display for Error {
 
 impl From<serde_json::Error> for Error {
     fn from(e: serde_json::Error) -> Error {
-        Error {
-            msg: format!("serde_json: {}", e),
-        }
+        Error::with_code(ErrorCode::Serialization, &format!("serde_json: {}", e))
     }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// How much a diagnostic matters.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Severity {
+    /// The spec is rejected.
+    Error,
+    /// The spec is accepted but likely not what was meant.
+    Warning,
+}
+
+/// Advisory check of a model, reported without rejecting it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Rule {
+    /// Entities, relations and attributes without a description.
+    MissingDescription,
+    /// Names sharing no word with the id they label, e.g. "Price" for `stock`.
+    NameMismatch,
+    /// Plurals other than the one derived from the singular, e.g. `categorys`.
+    Plural,
+    /// Entities neither related to nor extending other entities.
+    Unreachable,
+    /// Relations visible more widely than one of their entities, which
+    /// narrows them anyway.
+    UnusedVisibility,
+}
+
+/// A problem found in a spec, reported along with the others.
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub error: Error,
+    /// Lint rule reporting the diagnostic, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<Rule>,
+}
+
+impl Diagnostic {
+    pub fn error(error: Error) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            error,
+            rule: None,
+        }
+    }
+
+    pub fn warning(error: Error) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            error,
+            rule: None,
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! Expressions of computed attributes, e.g. `unit_price * quantity` or
+//! `first_name || ' ' || last_name`.
+//!
+//! Supported are integer, real and quoted text literals, attribute ids,
+//! `+ - * /` on numbers, `||` concatenation, parentheses and a few
+//! deterministic functions. `||` binds looser than arithmetic, unlike in
+//! SQLite, so expressions are rendered back fully parenthesized.
+
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::sql;
+
+/// Type of the value of an expression.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Integer,
+    Real,
+    Text,
+}
+
+impl Kind {
+    fn is_numeric(&self) -> bool {
+        matches!(self, Kind::Integer | Kind::Real)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Concat,
+}
+
+impl Operator {
+    fn as_str(&self) -> &str {
+        match self {
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Concat => "||",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    /// Reference to a sibling attribute.
+    Column(String),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, Operator, Box<Expr>),
+    /// Call of one of the supported functions, in lowercase.
+    Call(String, Vec<Expr>),
+}
+
+impl Expr {
+    /// Infer the type of the expression given the types of the attributes
+    /// it references.
+    pub fn kind(&self, column: &dyn Fn(&str) -> Result<Kind>) -> Result<Kind> {
+        match self {
+            Expr::Integer(_) => Ok(Kind::Integer),
+            Expr::Real(_) => Ok(Kind::Real),
+            Expr::Text(_) => Ok(Kind::Text),
+            Expr::Column(name) => column(name),
+            Expr::Negate(inner) => match inner.kind(column)? {
+                kind if kind.is_numeric() => Ok(kind),
+                _ => Err(Error::new("cannot negate text")),
+            },
+            Expr::Binary(left, Operator::Concat, right) => {
+                left.kind(column)?;
+                right.kind(column)?;
+                Ok(Kind::Text)
+            }
+            Expr::Binary(left, op, right) => match (left.kind(column)?, right.kind(column)?) {
+                (Kind::Integer, Kind::Integer) => Ok(Kind::Integer),
+                (l, r) if l.is_numeric() && r.is_numeric() => Ok(Kind::Real),
+                _ => Err(Error::new(&format!(
+                    "operator '{}' only applies to numbers",
+                    op.as_str()
+                ))),
+            },
+            Expr::Call(name, args) => {
+                let kinds = args
+                    .iter()
+                    .map(|arg| arg.kind(column))
+                    .collect::<Result<Vec<Kind>>>()?;
+                call_kind(name, &kinds)
+            }
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Binary(..) => write!(f, "({})", self),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+/// Renders the expression as SQL.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Integer(value) => write!(f, "{}", value),
+            Expr::Real(value) => write!(f, "{:?}", value),
+            Expr::Text(text) => write!(f, "{}", sql::quote(text)),
+            Expr::Column(name) => write!(f, "{}", name),
+            Expr::Negate(inner) => {
+                write!(f, "-")?;
+                inner.fmt_operand(f)
+            }
+            Expr::Binary(left, op, right) => {
+                left.fmt_operand(f)?;
+                write!(f, " {} ", op.as_str())?;
+                right.fmt_operand(f)
+            }
+            Expr::Call(name, args) => write!(
+                f,
+                "{}({})",
+                name,
+                args.iter()
+                    .map(|arg| arg.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// Type of the result of a supported function, given its arguments.
+fn call_kind(name: &str, args: &[Kind]) -> Result<Kind> {
+    let err = |expected: &str| -> Result<Kind> {
+        Err(Error::new(&format!(
+            "function '{}' expects {}",
+            name, expected
+        )))
+    };
+
+    match (name, args) {
+        ("abs", [kind]) if kind.is_numeric() => Ok(*kind),
+        ("abs", _) => err("one number"),
+        ("round", [kind]) if kind.is_numeric() => Ok(Kind::Real),
+        ("round", [kind, Kind::Integer]) if kind.is_numeric() => Ok(Kind::Real),
+        ("round", _) => err("a number and optional integer digits"),
+        ("lower" | "upper" | "trim", [Kind::Text]) => Ok(Kind::Text),
+        ("lower" | "upper" | "trim", _) => err("one text"),
+        ("length", [Kind::Text]) => Ok(Kind::Integer),
+        ("length", _) => err("one text"),
+        ("coalesce", [first, _, ..]) => {
+            args.iter()
+                .skip(1)
+                .try_fold(*first, |acc, kind| match (acc, *kind) {
+                    (l, r) if l == r => Ok(l),
+                    (l, r) if l.is_numeric() && r.is_numeric() => Ok(Kind::Real),
+                    _ => err("arguments of the same type"),
+                })
+        }
+        ("coalesce", _) => err("at least two arguments"),
+        _ => Err(Error::new(&format!("unknown function '{}'", name))),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Ident(String),
+    Operator(Operator),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                chars.next();
+            }
+            '0'..='9' | '.' => {
+                let mut number = String::new();
+                while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit() || **d == '.') {
+                    number.push(d);
+                    chars.next();
+                }
+                let invalid = || Error::new(&format!("invalid number '{}'", number));
+                tokens.push(match number.contains('.') {
+                    true => Token::Real(number.parse().map_err(|_| invalid())?),
+                    false => Token::Integer(number.parse().map_err(|_| invalid())?),
+                });
+            }
+            'a'..='z' | 'A'..='Z' | '_' => {
+                let mut ident = String::new();
+                while let Some(&d) = chars
+                    .peek()
+                    .filter(|d| d.is_ascii_alphanumeric() || **d == '_')
+                {
+                    ident.push(d);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            '\'' => {
+                chars.next();
+                let mut literal = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            literal.push('\'');
+                        }
+                        Some('\'') => break,
+                        Some(d) => literal.push(d),
+                        None => return Err(Error::new("unterminated text literal")),
+                    }
+                }
+                tokens.push(Token::Text(literal));
+            }
+            '|' => {
+                chars.next();
+                if chars.next() != Some('|') {
+                    return Err(Error::new("expected '||'"));
+                }
+                tokens.push(Token::Operator(Operator::Concat));
+            }
+            _ => {
+                chars.next();
+                tokens.push(match c {
+                    '+' => Token::Operator(Operator::Add),
+                    '-' => Token::Operator(Operator::Subtract),
+                    '*' => Token::Operator(Operator::Multiply),
+                    '/' => Token::Operator(Operator::Divide),
+                    '(' => Token::LeftParen,
+                    ')' => Token::RightParen,
+                    ',' => Token::Comma,
+                    other => return Err(Error::new(&format!("unexpected character '{}'", other))),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(Error::new(&format!("expected {}", what))),
+        }
+    }
+
+    /// Parse a chain of binary operators of the same precedence.
+    fn binary(
+        &mut self,
+        operators: &[Operator],
+        operand: fn(&mut Parser) -> Result<Expr>,
+    ) -> Result<Expr> {
+        let mut expr = operand(self)?;
+        while let Some(Token::Operator(op)) = self.peek() {
+            let op = *op;
+            if !operators.contains(&op) {
+                break;
+            }
+            self.next();
+            expr = Expr::Binary(Box::new(expr), op, Box::new(operand(self)?));
+        }
+        Ok(expr)
+    }
+
+    fn concat(&mut self) -> Result<Expr> {
+        self.binary(&[Operator::Concat], Parser::additive)
+    }
+
+    fn additive(&mut self) -> Result<Expr> {
+        self.binary(&[Operator::Add, Operator::Subtract], Parser::term)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        self.binary(&[Operator::Multiply, Operator::Divide], Parser::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if let Some(Token::Operator(Operator::Subtract)) = self.peek() {
+            self.next();
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Integer(value)) => Ok(Expr::Integer(value)),
+            Some(Token::Real(value)) => Ok(Expr::Real(value)),
+            Some(Token::Text(text)) => Ok(Expr::Text(text)),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LeftParen) => {
+                self.next();
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RightParen) {
+                    args.push(self.concat()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.next();
+                        args.push(self.concat()?);
+                    }
+                }
+                self.expect(Token::RightParen, "')' after arguments")?;
+                Ok(Expr::Call(name.to_lowercase(), args))
+            }
+            Some(Token::Ident(name)) => Ok(Expr::Column(name)),
+            Some(Token::LeftParen) => {
+                let expr = self.concat()?;
+                self.expect(Token::RightParen, "')'")?;
+                Ok(expr)
+            }
+            _ => Err(Error::new("expected a value")),
+        }
+    }
+}
+
+/// Parse the text of an expression.
+pub fn parse(text: &str) -> Result<Expr> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        position: 0,
+    };
+    let expr = parser.concat()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(Error::new(&format!("unexpected {:?}", token))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(name: &str) -> Result<Kind> {
+        match name {
+            "quantity" => Ok(Kind::Integer),
+            "unit_price" => Ok(Kind::Real),
+            "first_name" | "last_name" => Ok(Kind::Text),
+            _ => Err(Error::new(&format!("unknown '{}'", name))),
+        }
+    }
+
+    #[test]
+    fn expression_render() {
+        let render = |text: &str| parse(text).unwrap().to_string();
+        assert_eq!(render("unit_price*quantity"), "unit_price * quantity");
+        assert_eq!(render("1 + 2 * 3 - 4"), "(1 + (2 * 3)) - 4");
+        assert_eq!(render("(1 + 2) * -3.5"), "(1 + 2) * -3.5");
+        assert_eq!(
+            render("first_name || ' ' || UPPER(last_name)"),
+            "(first_name || ' ') || upper(last_name)"
+        );
+        assert_eq!(render("'it''s'"), "'it''s'");
+        assert_eq!(render("coalesce(quantity, 0)"), "coalesce(quantity, 0)");
+
+        assert!(parse("").is_err());
+        assert!(parse("1 +").is_err());
+        assert!(parse("(1").is_err());
+        assert!(parse("1 2").is_err());
+        assert!(parse("'open").is_err());
+        assert!(parse("a | b").is_err());
+        assert!(parse("a = b").is_err());
+        assert!(parse("1.2.3").is_err());
+    }
+
+    #[test]
+    fn expression_kinds() {
+        let kind = |text: &str| parse(text).unwrap().kind(&kinds);
+        assert_eq!(kind("quantity * 2").unwrap(), Kind::Integer);
+        assert_eq!(kind("unit_price * quantity").unwrap(), Kind::Real);
+        assert_eq!(kind("first_name || ' ' || quantity").unwrap(), Kind::Text);
+        assert_eq!(kind("length(last_name)").unwrap(), Kind::Integer);
+        assert_eq!(kind("round(unit_price, 2)").unwrap(), Kind::Real);
+        assert_eq!(kind("coalesce(quantity, 0.5)").unwrap(), Kind::Real);
+        assert!(kind("first_name * 2").is_err());
+        assert!(kind("-last_name").is_err());
+        assert!(kind("upper(quantity)").is_err());
+        assert!(kind("coalesce(quantity)").is_err());
+        assert!(kind("random()").is_err());
+        assert!(kind("missing + 1").is_err());
+    }
+}
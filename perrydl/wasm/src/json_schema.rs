@@ -1,4 +1,4 @@
-use crate::error::{Error, Result};
+use crate::error::{Error, ErrorCode, Result};
 use serde::{self, Deserialize, Serialize};
 use serde_json::{Map, Value};
 
@@ -16,16 +16,46 @@ pub struct JsonSchema {
     properties: Map<String, Value>,
     /// Required properties.
     required: Vec<String>,
+    /// Schemas values must also be valid against, e.g. that of a parent entity.
+    #[serde(rename = "allOf", skip_serializing_if = "Vec::is_empty")]
+    all_of: Vec<JsonSchema>,
 }
 
 #[derive(Default, Deserialize, Serialize)]
 pub struct Type {
-    #[serde(rename = "type")]
+    #[serde(rename = "type", skip_serializing_if = "Value::is_null")]
     ty: Value,
     #[serde(skip_serializing_if = "Option::is_none")]
     title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    enumeration: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maximum: Option<f64>,
+    #[serde(rename = "minLength", skip_serializing_if = "Option::is_none")]
+    min_length: Option<u32>,
+    #[serde(rename = "maxLength", skip_serializing_if = "Option::is_none")]
+    max_length: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    #[serde(rename = "contentEncoding", skip_serializing_if = "Option::is_none")]
+    content_encoding: Option<String>,
+    #[serde(rename = "contentMediaType", skip_serializing_if = "Option::is_none")]
+    content_media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<Box<Type>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default: Option<Value>,
+    #[serde(rename = "readOnly", skip_serializing_if = "Option::is_none")]
+    read_only: Option<bool>,
+    /// Keywords of an embedded sub-schema.
+    #[serde(flatten)]
+    inline: Map<String, Value>,
 }
 
 impl Type {
@@ -36,7 +66,100 @@ impl Type {
         }
     }
 
+    /// Array type whose items are all of the given type.
+    pub fn new_array(items: Type) -> Type {
+        Type {
+            items: Some(Box::new(items)),
+            ..Type::new_from_str("array")
+        }
+    }
+
+    /// Type described by a user provided sub-schema, which is inlined as is.
+    /// Its `type`, `enum` and `default` are kept apart so that nullable and
+    /// default values apply to them like to other types.
+    pub fn new_inline(schema: &Map<String, Value>) -> Type {
+        let mut inline = schema.clone();
+        let enumeration = match inline.remove("enum") {
+            Some(Value::Array(values)) => Some(values),
+            Some(other) => {
+                inline.insert("enum".to_owned(), other);
+                None
+            }
+            None => None,
+        };
+        Type {
+            ty: inline.remove("type").unwrap_or(Value::Null),
+            enumeration,
+            default: inline.remove("default"),
+            inline,
+            ..Default::default()
+        }
+    }
+
+    /// Set the format of string values, e.g. `date-time`.
+    pub fn with_format(mut self, format: Option<&str>) -> Type {
+        self.format = format.map(|f| f.to_owned());
+        self
+    }
+
+    /// Restrict the type to a fixed set of values.
+    pub fn with_enum(mut self, values: Vec<Value>) -> Type {
+        self.enumeration = Some(values);
+        self
+    }
+
+    /// Set inclusive bounds of numeric values.
+    pub fn with_bounds(mut self, minimum: Option<f64>, maximum: Option<f64>) -> Type {
+        self.minimum = minimum;
+        self.maximum = maximum;
+        self
+    }
+
+    /// Set length bounds of string values.
+    pub fn with_length(mut self, min_length: Option<u32>, max_length: Option<u32>) -> Type {
+        self.min_length = min_length;
+        self.max_length = max_length;
+        self
+    }
+
+    /// Set the regular expression string values must match.
+    pub fn with_pattern(mut self, pattern: Option<String>) -> Type {
+        self.pattern = pattern;
+        self
+    }
+
+    /// Describe string values as encoded binary content, of a given media
+    /// type when only one is allowed. Like `contentMediaType` itself, which
+    /// has no counterpart for several types, this is an annotation only.
+    pub fn with_content(mut self, encoding: &str, media_types: &[String]) -> Type {
+        self.content_encoding = Some(encoding.to_owned());
+        if let [media_type] = media_types {
+            self.content_media_type = Some(media_type.clone());
+        }
+        self
+    }
+
+    /// Set the value forms should prefill, keeping the current one if none.
+    pub fn with_default(mut self, default: Option<Value>) -> Type {
+        if default.is_some() {
+            self.default = default;
+        }
+        self
+    }
+
+    /// Mark values as managed by the server, e.g. computed ones.
+    pub fn with_read_only(mut self, read_only: bool) -> Type {
+        self.read_only = Some(read_only).filter(|r| *r);
+        self
+    }
+
     fn make_nullable(&mut self) {
+        if let Some(values) = self.enumeration.as_mut() {
+            if !values.contains(&Value::Null) {
+                values.push(Value::Null);
+            }
+        }
+
         let inner = self.ty.clone();
         match inner {
             Value::String(s) => {
@@ -48,6 +171,14 @@ impl Type {
                     self.ty.as_array_mut().unwrap().push(null);
                 }
             }
+            // sub-schema without a type, accept null as an alternative
+            Value::Null if !self.inline.is_empty() => {
+                let inner = std::mem::take(&mut self.inline);
+                self.inline.insert(
+                    "anyOf".to_owned(),
+                    serde_json::json!([{ "type": "null" }, inner]),
+                );
+            }
             _ => {} // this should not happen with valid json-schema
         }
     }
@@ -60,12 +191,21 @@ impl JsonSchema {
             schema: DEFAULT_SCHEMA.to_owned(),
             properties: Map::new(),
             required: Vec::new(),
+            all_of: Vec::new(),
         }
     }
 
+    /// Require values to also be valid against another schema.
+    pub fn add_all_of(&mut self, schema: JsonSchema) {
+        self.all_of.push(schema);
+    }
+
     pub fn add_property(&mut self, id: &str, ty: Type, required: bool) -> Result<()> {
         if self.properties.get(id).is_some() {
-            return Err(Error::new(&format!("property {} already exists", id)));
+            return Err(Error::with_code(
+                ErrorCode::Conflict,
+                &format!("property {} already exists", id),
+            ));
         }
 
         let mut inner_type = ty;
@@ -143,6 +283,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn json_schema_nullable_enum() {
+        let mut ty = Type::new_from_str("string").with_enum(vec![
+            Value::String("new".to_owned()),
+            Value::String("shipped".to_owned()),
+        ]);
+
+        ty.make_nullable();
+
+        assert_eq!(
+            serde_json::to_value(&ty).unwrap(),
+            serde_json::json!({
+                "type": ["string", "null"],
+                "enum": ["new", "shipped", null]
+            })
+        );
+    }
+
+    #[test]
+    fn json_schema_nullable_inline() {
+        let sub = serde_json::json!({ "type": "object", "required": ["width"] });
+        let mut ty = Type::new_inline(sub.as_object().unwrap());
+        ty.make_nullable();
+        assert_eq!(
+            serde_json::to_value(&ty).unwrap(),
+            serde_json::json!({ "type": ["object", "null"], "required": ["width"] })
+        );
+
+        let sub = serde_json::json!({ "enum": [1, 2] });
+        let mut ty = Type::new_inline(sub.as_object().unwrap());
+        ty.make_nullable();
+        assert_eq!(
+            serde_json::to_value(&ty).unwrap(),
+            serde_json::json!({ "enum": [1, 2, null] })
+        );
+
+        let sub = serde_json::json!({ "minimum": 1, "default": 2 });
+        let mut ty = Type::new_inline(sub.as_object().unwrap()).with_default(None);
+        ty.make_nullable();
+        assert_eq!(
+            serde_json::to_string(&ty).unwrap(),
+            r#"{"default":2,"anyOf":[{"type":"null"},{"minimum":1}]}"#
+        );
+        let ty = Type::new_inline(sub.as_object().unwrap()).with_default(Some(3.into()));
+        assert_eq!(
+            serde_json::to_string(&ty).unwrap(),
+            r#"{"default":3,"minimum":1}"#
+        );
+
+        let mut ty = Type::new_inline(&Map::new());
+        ty.make_nullable();
+        assert_eq!(serde_json::to_value(&ty).unwrap(), serde_json::json!({}));
+    }
+
     #[test]
     fn json_schema_object() {
         let mut schema = JsonSchema::new("part");
@@ -1,8 +1,11 @@
 mod common;
 mod entity;
 mod error;
+mod expression;
 mod json_schema;
+mod lint;
 mod model;
+mod physical;
 mod relation;
 mod sql;
 mod urn;
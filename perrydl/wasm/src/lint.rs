@@ -0,0 +1,338 @@
+use crate::common::Visibility;
+use crate::entity::Entity;
+use crate::error::{Diagnostic, Error, ErrorCode, Rule, Severity};
+use crate::relation::Relation;
+use crate::urn;
+use inflector::Inflector;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How the diagnostics of a rule are reported.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Level {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn severity(&self) -> Option<Severity> {
+        match self {
+            Level::Off => None,
+            Level::Warning => Some(Severity::Warning),
+            Level::Error => Some(Severity::Error),
+        }
+    }
+}
+
+/// Levels of the rules, those left out are reported as warnings.
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub rules: BTreeMap<Rule, Level>,
+}
+
+impl Config {
+    fn severity(&self, rule: Rule) -> Option<Severity> {
+        self.rules
+            .get(&rule)
+            .copied()
+            .unwrap_or(Level::Warning)
+            .severity()
+    }
+}
+
+/// Lowercase letters and digits of a text, e.g. `pricedby` for "Priced By".
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Whether a name shares a word with the id it labels, e.g. "Stock Units"
+/// and `stock`. Words shorter than three letters are only compared whole.
+fn matches_id(name: &str, id: &str) -> bool {
+    let (name_text, id_text) = (normalize(name), normalize(id));
+    let words = |text: &str, other: &str| {
+        text.to_snake_case()
+            .split('_')
+            .filter(|word| word.len() >= 3)
+            .any(|word| other.contains(&normalize(word)))
+    };
+    name_text == id_text || words(name, &id_text) || words(id, &name_text)
+}
+
+/// How widely a visibility exposes data.
+fn width(visibility: &Visibility) -> u8 {
+    match visibility {
+        Visibility::User => 0,
+        Visibility::Tenant => 1,
+        Visibility::Global => 2,
+    }
+}
+
+fn issue(urn: &str, pointer: &str, msg: &str) -> Error {
+    Error::with_code(ErrorCode::Lint, msg)
+        .with_urn(urn)
+        .at(pointer)
+}
+
+/// Check entities and relations against the rules enabled in config.
+pub fn lint(entities: &[Entity], relations: &[Relation], config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut report = |rule: Rule, error: Error| {
+        if let Some(severity) = config.severity(rule) {
+            diagnostics.push(Diagnostic {
+                severity,
+                error,
+                rule: Some(rule),
+            });
+        }
+    };
+
+    let described = entities
+        .iter()
+        .enumerate()
+        .map(|(i, ent)| {
+            let name = (&ent.name, ent.singular.as_str(), "singular");
+            (
+                &ent.urn,
+                format!("/entities/{}", i),
+                &ent.description,
+                name,
+                &ent.attributes,
+            )
+        })
+        .chain(relations.iter().enumerate().map(|(i, rel)| {
+            let name = (&rel.name, urn::basename(&rel.urn), "urn");
+            (
+                &rel.urn,
+                format!("/relations/{}", i),
+                &rel.description,
+                name,
+                &rel.attributes,
+            )
+        }));
+    for (urn, pointer, description, (name, id, id_field), attributes) in described {
+        if description.trim().is_empty() {
+            report(
+                Rule::MissingDescription,
+                issue(
+                    urn,
+                    &format!("{}/description", pointer),
+                    &format!("'{}' has no description", urn),
+                ),
+            );
+        }
+        if !matches_id(name, id) {
+            report(
+                Rule::NameMismatch,
+                issue(
+                    urn,
+                    &format!("{}/name", pointer),
+                    &format!(
+                        "name '{}' of '{}' does not match its {}",
+                        name, urn, id_field
+                    ),
+                ),
+            );
+        }
+        for (j, attr) in attributes.iter().enumerate() {
+            let pointer = format!("{}/attributes/{}", pointer, j);
+            if attr.description.trim().is_empty() {
+                report(
+                    Rule::MissingDescription,
+                    issue(
+                        urn,
+                        &format!("{}/description", pointer),
+                        &format!("attribute '{}' of '{}' has no description", attr.id, urn),
+                    )
+                    .with_attribute(&attr.id),
+                );
+            }
+            if !matches_id(&attr.name, &attr.id) {
+                report(
+                    Rule::NameMismatch,
+                    issue(
+                        urn,
+                        &format!("{}/name", pointer),
+                        &format!(
+                            "name '{}' of attribute '{}' of '{}' does not match its id",
+                            attr.name, attr.id, urn
+                        ),
+                    )
+                    .with_attribute(&attr.id),
+                );
+            }
+        }
+    }
+
+    for (i, entity) in entities.iter().enumerate() {
+        let plural = entity.singular.to_plural();
+        if entity.plural != plural {
+            report(
+                Rule::Plural,
+                issue(
+                    &entity.urn,
+                    &format!("/entities/{}/plural", i),
+                    &format!(
+                        "plural '{}' of '{}' differs from '{}'",
+                        entity.plural, entity.urn, plural
+                    ),
+                )
+                .with_suggestion(&plural),
+            );
+        }
+    }
+
+    // Imported entities are Global, so never narrower than a relation
+    for (i, relation) in relations.iter().enumerate() {
+        let narrowest = entities
+            .iter()
+            .filter(|ent| [&relation.origin, &relation.destination].contains(&&ent.urn))
+            .min_by_key(|ent| width(&ent.visibility))
+            .filter(|ent| width(&ent.visibility) < width(&relation.visibility));
+        if let Some(entity) = narrowest {
+            report(
+                Rule::UnusedVisibility,
+                issue(
+                    &relation.urn,
+                    &format!("/relations/{}/visibility", i),
+                    &format!(
+                        "relation '{}' is {:?} but entity '{}' is {:?}",
+                        relation.urn, relation.visibility, entity.urn, entity.visibility
+                    ),
+                )
+                .with_suggestion(&format!("{:?}", entity.visibility)),
+            );
+        }
+    }
+
+    // A lone entity has nothing to be related to
+    if entities.len() > 1 {
+        let reachable: BTreeSet<&str> = relations
+            .iter()
+            .flat_map(|rel| vec![rel.origin.as_str(), rel.destination.as_str()])
+            .chain(entities.iter().flat_map(|ent| match &ent.extends {
+                Some(parent) => vec![parent.as_str(), ent.urn.as_str()],
+                None => vec![],
+            }))
+            .collect();
+        for (i, entity) in entities.iter().enumerate() {
+            if !reachable.contains(entity.urn.as_str()) {
+                report(
+                    Rule::Unreachable,
+                    issue(
+                        &entity.urn,
+                        &format!("/entities/{}", i),
+                        &format!(
+                            "entity '{}' is neither related to nor extends other entities",
+                            entity.urn
+                        ),
+                    ),
+                );
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(urn: &str, singular: &str, plural: &str, name: &str) -> Entity {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "urn": urn,
+            "singular": singular,
+            "plural": plural,
+            "name": name,
+            "description": "",
+            "visibility": "Tenant",
+            "attributes": [
+                {
+                    "id": "label",
+                    "name": "Label",
+                    "description": "Short text naming it",
+                    "type": "string"
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn relation(urn: &str, name: &str, origin: &str, destination: &str) -> Relation {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "urn": urn,
+            "name": name,
+            "description": "Relates them",
+            "visibility": "Global",
+            "origin": origin,
+            "destination": destination,
+            "cardinality": "OneToMany",
+            "attributes": [],
+            "required": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn lint_rules() {
+        let entities = vec![
+            entity("sampleperry:part", "part", "parts", "Part"),
+            entity("sampleperry:category", "category", "categorys", "Category"),
+            entity("sampleperry:country", "country", "countries", "Nation"),
+        ];
+        let relations = vec![relation(
+            "sampleperry:categorisedby",
+            "Categorised By",
+            "sampleperry:category",
+            "sampleperry:part",
+        )];
+
+        let diagnostics = lint(&entities, &relations, &Config::default());
+        let summary: Vec<(Rule, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.rule.unwrap(), d.error.pointer()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Rule::MissingDescription, "/entities/0/description"),
+                (Rule::MissingDescription, "/entities/1/description"),
+                (Rule::MissingDescription, "/entities/2/description"),
+                (Rule::NameMismatch, "/entities/2/name"),
+                (Rule::Plural, "/entities/1/plural"),
+                (Rule::UnusedVisibility, "/relations/0/visibility"),
+                (Rule::Unreachable, "/entities/2"),
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.severity == Severity::Warning && d.error.code() == ErrorCode::Lint));
+        assert_eq!(diagnostics[4].error.suggestion(), Some("categories"));
+        assert_eq!(diagnostics[5].error.suggestion(), Some("Tenant"));
+
+        let config: Config = serde_json::from_str(
+            r#"{"rules": {"MissingDescription": "Off", "Unreachable": "Error"}}"#,
+        )
+        .unwrap();
+        let diagnostics = lint(&entities, &relations, &config);
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!(diagnostics[3].rule, Some(Rule::Unreachable));
+        assert_eq!(diagnostics[3].severity, Severity::Error);
+    }
+
+    #[test]
+    fn lint_matches_id() {
+        assert!(matches_id("Stock Units", "stock"));
+        assert!(matches_id("Priced By", "pricedby"));
+        assert!(matches_id("Price per unit", "unit_price"));
+        assert!(matches_id("ID", "id"));
+        assert!(!matches_id("Price", "stock"));
+    }
+}
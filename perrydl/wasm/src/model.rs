@@ -1,7 +1,10 @@
-use crate::common::UniqueConstraint;
-use crate::entity::Entity;
-use crate::error::{Error, Result};
-use crate::relation::{Cardinality, Relation};
+use crate::common::{Attribute, Identity, UniqueConstraint, Visibility};
+use crate::entity::{self, Entity, Inheritance};
+use crate::error::{Diagnostic, Error, ErrorCode, Result, Severity};
+use crate::json_schema::{self, JsonSchema};
+use crate::lint;
+use crate::physical;
+use crate::relation::{self, Cardinality, JoinKey, Relation};
 use crate::sql;
 use crate::urn;
 use serde::{Deserialize, Serialize};
@@ -12,15 +15,66 @@ pub struct SerdeModel {
     tenant: String,
     entities: Vec<Entity>,
     relations: Vec<Relation>,
+    /// Identity strategy of entities that do not define their own.
+    #[serde(default)]
+    identity: Identity,
+    /// Urns of Global entities of other models which relations may refer to,
+    /// looked up among the entities of the models owning them. Their tables
+    /// are generated by those models, so must already exist wherever the
+    /// tables of this one are created.
+    #[serde(default)]
+    imports: Vec<String>,
+}
+
+/// Physical names of a relation.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RelationMapping {
+    pub urn: String,
+    /// Table storing the relation, that of the destination entity for
+    /// OneToMany and OneToOne relations.
+    pub table: String,
+    /// Column referencing the origin.
+    pub origin_key: String,
+    /// Column referencing the destination, its `id` for OneToMany and
+    /// OneToOne relations.
+    pub destination_key: String,
+}
+
+/// Direction a relation is navigated in.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Direction {
+    /// From the origin to the destination.
+    Forward,
+    /// From the destination to the origin.
+    Inverse,
+}
+
+/// A relation as navigated from one of its entities.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Navigation {
+    pub relation: String,
+    pub direction: Direction,
+    pub name: String,
+    pub description: String,
+    /// Entity at the other end.
+    pub target: String,
+    pub cardinality: Cardinality,
 }
 
 /// Data model of a specific tenant
 #[derive(Debug)]
 pub struct Model {
     tenant: String,
+    /// Identity strategy of entities that do not define their own, and
+    /// of join tables keyed by an id of their own.
+    identity: Identity,
     entities: Vec<Entity>,
     relations: Vec<Relation>,
+    imports: Vec<Entity>,
+    /// Position of each import in the submitted list of urns.
+    imported_at: Vec<usize>,
     by_urn: BTreeMap<String, usize>,
+    by_urn_imports: BTreeMap<String, usize>,
     by_singular: BTreeMap<String, usize>,
     by_plural: BTreeMap<String, usize>,
     by_urn_relations: BTreeMap<String, usize>,
@@ -28,8 +82,97 @@ pub struct Model {
 
 impl Model {
     pub fn new(serde_model: SerdeModel) -> Result<Model> {
+        Model::importing(serde_model, &[])
+    }
+
+    /// Build a model whose imports are entities of the given models.
+    pub fn importing(serde_model: SerdeModel, owners: &[Model]) -> Result<Model> {
+        let (imports, unresolved) = Model::resolve_imports(&serde_model.imports, owners);
+        Error::first_of(unresolved)?;
+        let model = Model::index(serde_model, imports);
+
+        // perform validations
+        model.validate()?;
+
+        Ok(model)
+    }
+
+    /// Look up the entities a model imports among those the given models
+    /// define, along with their position in the list of urns. Urns not
+    /// found are reported instead.
+    fn resolve_imports(urns: &[String], owners: &[Model]) -> (Vec<(usize, Entity)>, Vec<Error>) {
+        let mut imports = Vec::new();
+        let mut errors = Vec::new();
+        for (i, urn) in urns.iter().enumerate() {
+            let found = owners.iter().find_map(|owner| {
+                let index = owner.by_urn.get(urn)?;
+                Some(owner.entities[*index].clone())
+            });
+            match found {
+                Some(entity) => imports.push((i, entity)),
+                None => errors.push(
+                    Error::with_code(
+                        ErrorCode::NotFound,
+                        &format!("imported entity '{}' is not found in other models", urn),
+                    )
+                    .with_urn(urn)
+                    .at(&format!("/imports/{}", i)),
+                ),
+            }
+        }
+        (imports, errors)
+    }
+
+    /// Validate a model, reporting every problem found instead of the first,
+    /// followed by warnings about names of the generated schema that targets
+    /// may misread and about declarations that have no effect.
+    pub fn diagnose(serde_model: SerdeModel) -> Vec<Diagnostic> {
+        Model::diagnose_importing(serde_model, &[])
+    }
+
+    /// Diagnose a model whose imports are entities of the given models.
+    pub fn diagnose_importing(serde_model: SerdeModel, owners: &[Model]) -> Vec<Diagnostic> {
+        let (imports, unresolved) = Model::resolve_imports(&serde_model.imports, owners);
+        let model = Model::index(serde_model, imports);
+        let errors: Vec<Error> = unresolved.into_iter().chain(model.errors()).collect();
+        let physical = match errors.is_empty() {
+            true => model
+                .physical_diagnostics()
+                .into_iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+                .collect(),
+            false => vec![],
+        };
+        errors
+            .into_iter()
+            .map(Diagnostic::error)
+            .chain(physical)
+            .chain(model.warnings().into_iter().map(Diagnostic::warning))
+            .collect()
+    }
+
+    /// Advisory diagnostics of the model for the rules enabled in config.
+    pub fn lint(&self, config: &lint::Config) -> Vec<Diagnostic> {
+        lint::lint(&self.entities, &self.relations, config)
+    }
+
+    /// Build a model and its indexes without validating it.
+    fn index(serde_model: SerdeModel, imports: Vec<(usize, Entity)>) -> Model {
+        let identity = serde_model.identity;
         let entities = serde_model.entities;
-        let relations = serde_model.relations;
+        let mut relations = serde_model.relations;
+
+        // Relations stored alike between the same entities as an earlier one
+        // need qualified names, the first keeps its plain names.
+        let mut storages: BTreeSet<(String, String, bool)> = BTreeSet::new();
+        relations.iter_mut().for_each(|rel| {
+            let key = (
+                rel.origin.clone(),
+                rel.destination.clone(),
+                rel.cardinality.is_foreign_key(),
+            );
+            rel.qualified = !storages.insert(key);
+        });
 
         // Index entities by urn.
         let by_urn: BTreeMap<String, usize> = entities
@@ -50,109 +193,753 @@ impl Model {
             .map(|(i, ent)| (ent.plural.clone(), i))
             .collect();
 
+        let (imported_at, imports): (Vec<usize>, Vec<Entity>) = imports.into_iter().unzip();
+        let by_urn_imports: BTreeMap<String, usize> = imports
+            .iter()
+            .enumerate()
+            .map(|(i, ent)| (ent.urn.clone(), i))
+            .collect();
+
         let by_urn_relations: BTreeMap<String, usize> = relations
             .iter()
             .enumerate()
             .map(|(i, rel)| (rel.urn.clone(), i))
             .collect();
 
-        let model = Model {
+        let mut model = Model {
             tenant: serde_model.tenant,
+            identity,
             relations,
             entities,
+            imports,
+            imported_at,
             by_urn,
+            by_urn_imports,
             by_singular,
             by_plural,
             by_urn_relations,
         };
 
-        // perform validations
-        model.validate()?;
+        // Entities inherit the identity strategy of their root, then of the model.
+        let identities: Vec<Identity> = model
+            .entities
+            .iter()
+            .map(|ent| {
+                ent.identity
+                    .or(model.root(ent).identity)
+                    .unwrap_or(identity)
+            })
+            .collect();
+        model
+            .entities
+            .iter_mut()
+            .zip(identities)
+            .for_each(|(ent, identity)| ent.identity = Some(identity));
 
-        Ok(model)
+        model
     }
 
-    fn relations_of_entity(&self, urn: &str, cardinality: Cardinality) -> Vec<usize> {
+    fn relations_of_entity(&self, urn: &str, cardinalities: &[Cardinality]) -> Vec<usize> {
         self.relations
             .iter()
             .enumerate()
-            .filter(|(_, rel)| rel.is_destination(urn, cardinality))
+            .filter(|(_, rel)| cardinalities.iter().any(|c| rel.is_destination(urn, *c)))
             .map(|(i, _)| i)
             .collect()
     }
 
+    /// Entities an entity extends, from its parent up to the root.
+    /// Stops at a parent that is not found or when going around a cycle.
+    fn ancestors(&self, entity_ref: &Entity) -> Vec<&Entity> {
+        let mut ancestors: Vec<&Entity> = Vec::new();
+        let mut current = entity_ref;
+        while let Some(index) = current
+            .extends
+            .as_ref()
+            .and_then(|urn| self.by_urn.get(urn))
+        {
+            let parent = &self.entities[*index];
+            if parent.urn == entity_ref.urn || ancestors.len() == self.entities.len() {
+                break;
+            }
+            ancestors.push(parent);
+            current = parent;
+        }
+        ancestors
+    }
+
+    /// Root of the hierarchy of an entity, the entity itself if it extends none.
+    fn root<'a>(&'a self, entity_ref: &'a Entity) -> &'a Entity {
+        self.ancestors(entity_ref)
+            .last()
+            .copied()
+            .unwrap_or(entity_ref)
+    }
+
+    /// Entities extending an entity, directly or not.
+    fn subtypes(&self, entity_ref: &Entity) -> Vec<&Entity> {
+        self.entities
+            .iter()
+            .filter(|ent| {
+                self.ancestors(ent)
+                    .iter()
+                    .any(|ancestor| ancestor.urn == entity_ref.urn)
+            })
+            .collect()
+    }
+
+    fn inheritance(&self, entity_ref: &Entity) -> Inheritance {
+        self.root(entity_ref).inheritance.unwrap_or_default()
+    }
+
+    /// Entity whose table stores an entity, the root of a single table hierarchy.
+    fn table_owner<'a>(&'a self, entity_ref: &'a Entity) -> &'a Entity {
+        match self.inheritance(entity_ref) {
+            Inheritance::SingleTable => self.root(entity_ref),
+            Inheritance::Joined => entity_ref,
+        }
+    }
+
+    /// Entities stored in the table of an entity, itself first.
+    fn stored_entities<'a>(&'a self, entity_ref: &'a Entity) -> Vec<&'a Entity> {
+        let mut stored = vec![entity_ref];
+        if entity_ref.extends.is_none() && self.inheritance(entity_ref) == Inheritance::SingleTable
+        {
+            stored.extend(self.subtypes(entity_ref));
+        }
+        stored
+    }
+
+    /// Discriminator values of the rows of an entity, its own and those of its subtypes.
+    fn discriminator_values(&self, entity_ref: &Entity) -> Vec<String> {
+        std::iter::once(entity_ref)
+            .chain(self.subtypes(entity_ref))
+            .map(|ent| ent.singular.clone())
+            .collect()
+    }
+
+    /// Check keeping a column of a subtype stored in a single table not null
+    /// on the rows of that subtype.
+    fn discriminator_check(&self, entity_ref: &Entity, column: &str) -> String {
+        let values: Vec<String> = self
+            .discriminator_values(entity_ref)
+            .iter()
+            .map(|value| format!("'{}'", value))
+            .collect();
+        format!(
+            "{} NOT IN ({}) OR {} IS NOT NULL",
+            entity::DISCRIMINATOR,
+            values.join(", "),
+            column
+        )
+    }
+
     /// Validates:
     /// * each entity and relation independently.
+    /// * that entities extend entities of their namespace without cycles,
+    ///   sharing their identity and without redefining inherited attributes.
+    /// * that urns, ids and entity names (singulars and plurals alike) are unique,
+    ///   imports included.
     /// * that entities and relations basenames do not collide.
-    /// * that each relation has a valid origin/destination entity.
+    /// * that each relation has a valid origin/destination entity,
+    ///   either in its namespace or imported.
+    /// * that imports are Global entities and that relations store foreign
+    ///   keys only in tables of the model.
     /// * that an entity is at most once a destination in a OneToMany relation.
+    /// * that unique constraints only use relations the entity holds a foreign key of.
+    /// * that table names and column names within tables do not collide.
+    /// * that generated tables, columns and triggers are neither reserved
+    ///   in SQLite nor told apart by case only.
     pub fn validate(&self) -> Result<()> {
-        self.entities
-            .iter()
-            .map(|ent| ent.validate())
-            .collect::<Result<Vec<()>>>()?;
+        Error::first_of(self.errors())
+    }
 
-        self.relations
-            .iter()
-            .map(|rel| rel.validate())
-            .collect::<Result<Vec<()>>>()?;
+    /// Every problem of the model, where `validate` stops at the first.
+    /// Checks that depend on relations having both entities skip those
+    /// that do not.
+    pub fn errors(&self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        let fail = |code: ErrorCode, urn: &str, pointer: String, msg: String| {
+            Error::with_code(code, &msg).with_urn(urn).at(&pointer)
+        };
 
-        // Basenames cannot collide among entities and relations
-        let mut basenames: BTreeSet<&str> = BTreeSet::new();
-        self.entities
-            .iter()
-            .map(|ent| urn::basename(&ent.urn))
-            .chain(self.relations.iter().map(|rel| urn::basename(&rel.urn)))
-            .find(|name| !basenames.insert(name))
-            .map(|name| {
-                Err(Error::new(&format!(
-                    "basename '{}' occurs more than once",
-                    name
-                )))
-            })
-            .unwrap_or(Ok(()))?;
+        for (i, ent) in self.entities.iter().enumerate() {
+            let pointer = format!("/entities/{}", i);
+            errors.extend(ent.errors().into_iter().map(|e| e.at(&pointer)));
+        }
 
-        // Validate origins and destinations of relations
-        self.relations
+        for (i, rel) in self.relations.iter().enumerate() {
+            let pointer = format!("/relations/{}", i);
+            errors.extend(rel.errors().into_iter().map(|e| e.at(&pointer)));
+        }
+
+        errors.extend(self.index_errors());
+
+        // Imports were checked by the models owning them
+        for (import, i) in self.imports.iter().zip(&self.imported_at) {
+            let pointer = format!("/imports/{}", i);
+            if import.visibility != Visibility::Global {
+                errors.push(fail(
+                    ErrorCode::InvalidEntity,
+                    &import.urn,
+                    pointer.clone(),
+                    format!(
+                        "imported entity '{}' must have Global visibility",
+                        import.urn
+                    ),
+                ));
+            }
+            if self.by_urn.contains_key(&import.urn) {
+                errors.push(fail(
+                    ErrorCode::Conflict,
+                    &import.urn,
+                    pointer.clone(),
+                    format!(
+                        "imported entity '{}' is also defined in the model",
+                        import.urn
+                    ),
+                ));
+            }
+            if import.extends.is_some() {
+                errors.push(fail(
+                    ErrorCode::InvalidInheritance,
+                    &import.urn,
+                    pointer,
+                    format!(
+                        "imported entity '{}' cannot extend another entity",
+                        import.urn
+                    ),
+                ));
+            }
+        }
+
+        // Entities extend entities of their namespace
+        for (i, entity) in self.entities.iter().enumerate() {
+            if let Some(parent) = &entity.extends {
+                let pointer = format!("/entities/{}/extends", i);
+                if !self.by_urn.contains_key(parent) {
+                    errors.push(fail(
+                        ErrorCode::NotFound,
+                        &entity.urn,
+                        pointer,
+                        format!(
+                            "entity '{}' extends '{}' which is not found",
+                            entity.urn, parent
+                        ),
+                    ));
+                } else if urn::not_basename(parent) != urn::not_basename(&entity.urn) {
+                    errors.push(fail(
+                        ErrorCode::InvalidInheritance,
+                        &entity.urn,
+                        pointer,
+                        format!(
+                            "entity '{}' does not share the namespace of '{}'",
+                            parent, entity.urn
+                        ),
+                    ));
+                }
+            }
+        }
+
+        for (i, entity) in self.entities.iter().enumerate() {
+            let pointer = format!("/entities/{}", i);
+            let root = self.root(entity);
+            if root.extends.is_some() {
+                errors.push(fail(
+                    ErrorCode::InvalidInheritance,
+                    &entity.urn,
+                    format!("{}/extends", pointer),
+                    format!("entity '{}' is part of an inheritance cycle", entity.urn),
+                ));
+                continue;
+            }
+            if entity.identity != root.identity {
+                errors.push(fail(
+                    ErrorCode::InvalidInheritance,
+                    &entity.urn,
+                    format!("{}/identity", pointer),
+                    format!(
+                        "entity '{}' must share the identity of '{}'",
+                        entity.urn, root.urn
+                    ),
+                ));
+            }
+
+            let inherited: Vec<&Attribute> = self
+                .ancestors(entity)
+                .iter()
+                .flat_map(|ent| ent.attributes.iter())
+                .collect();
+            for (j, attr) in entity
+                .attributes
+                .iter()
+                .enumerate()
+                .filter(|(_, attr)| inherited.iter().any(|other| other.id == attr.id))
+            {
+                errors.push(
+                    Error::with_code(
+                        ErrorCode::Conflict,
+                        &format!(
+                            "in entity '{}', attribute '{}' conflicts with an inherited one",
+                            entity.urn, attr.id
+                        ),
+                    )
+                    .with_urn(&entity.urn)
+                    .with_attribute(&attr.id)
+                    .at(&format!("{}/attributes/{}/id", pointer, j)),
+                );
+            }
+
+            // Constraints of subtypes mapped to tables of their own cannot span tables
+            let joined = self.inheritance(entity) == Inheritance::Joined;
+            for (j, uc) in entity.unique_constraints.iter().enumerate() {
+                for (k, id) in uc.attributes.iter().enumerate() {
+                    // Lists are stored as JSON text, unique constraints of
+                    // roots are checked along the entity
+                    let list = entity
+                        .attributes
+                        .iter()
+                        .chain(inherited.iter().copied())
+                        .any(|attr| attr.id == *id && attr.list);
+                    if list && entity.extends.is_some() {
+                        errors.push(fail(
+                            ErrorCode::InvalidAttribute,
+                            &entity.urn,
+                            format!("{}/unique_constraints/{}/attributes/{}", pointer, j, k),
+                            format!(
+                                "in entity '{}', list attribute '{}' cannot be unique",
+                                entity.urn, id
+                            ),
+                        ));
+                        continue;
+                    }
+                    if entity.attributes.iter().any(|attr| attr.id == *id) {
+                        continue;
+                    }
+                    let (code, message) = match inherited.iter().any(|attr| attr.id == *id) {
+                        true if joined => (
+                            ErrorCode::InvalidInheritance,
+                            "is stored in the table of a parent",
+                        ),
+                        true => continue,
+                        false => (ErrorCode::NotFound, "is not found"),
+                    };
+                    errors.push(fail(
+                        code,
+                        &entity.urn,
+                        format!("{}/unique_constraints/{}/attributes/{}", pointer, j, k),
+                        format!(
+                            "in entity '{}', unique constraint attribute '{}' {}",
+                            entity.urn, id, message
+                        ),
+                    ));
+                }
+            }
+        }
+
+        // Basenames cannot collide among entities and relations,
+        // repeated urns being reported along the indexes
+        let mut basenames: BTreeMap<&str, &str> = BTreeMap::new();
+        for urn in self
+            .entities
             .iter()
-            .find(|rel| {
-                !self.by_urn.contains_key(&rel.origin)
-                    || !self.by_urn.contains_key(&rel.destination)
-            })
-            .map(|rel| {
-                Err(Error::new(&format!(
-                    "relation '{}' origin or destination not found",
-                    rel.urn
-                )))
-            })
-            .unwrap_or(Ok(()))?;
+            .chain(self.imports.iter())
+            .map(|ent| ent.urn.as_str())
+            .chain(self.relations.iter().map(|rel| rel.urn.as_str()))
+            .filter(|urn| *basenames.entry(urn::basename(urn)).or_insert(urn) != *urn)
+        {
+            let pointer = match self.by_urn_relations.contains_key(urn) {
+                true => format!("{}/urn", self.relation_pointer(urn)),
+                false => self.entity_field_pointer(urn, "urn"),
+            };
+            errors.push(fail(
+                ErrorCode::Conflict,
+                urn,
+                pointer,
+                format!("basename '{}' occurs more than once", urn::basename(urn)),
+            ));
+        }
 
-        // Validate that all relations exist in all entities' UniqueConstraints
+        // Validate origins and destinations of relations, checks below
+        // skipping relations without both
+        for rel in &self.relations {
+            for (role, urn) in &[("origin", &rel.origin), ("destination", &rel.destination)] {
+                if self.entity(urn).is_none() {
+                    errors.push(fail(
+                        ErrorCode::NotFound,
+                        &rel.urn,
+                        format!("{}/{}", self.relation_pointer(&rel.urn), role),
+                        format!("relation '{}' origin or destination not found", rel.urn),
+                    ));
+                }
+            }
+        }
+        for rel in &self.relations {
+            if self.entity(&rel.origin).is_none() || self.entity(&rel.destination).is_none() {
+                continue;
+            }
+            let pointer = self.relation_pointer(&rel.urn);
+            for (role, urn) in &[("origin", &rel.origin), ("destination", &rel.destination)] {
+                let local = self.by_urn.contains_key(*urn);
+                if local && urn::not_basename(urn) != urn::not_basename(&rel.urn) {
+                    errors.push(fail(
+                        ErrorCode::InvalidRelation,
+                        &rel.urn,
+                        format!("{}/{}", pointer, role),
+                        format!(
+                            "{} '{}' does not share the namespace of '{}'",
+                            role, urn, rel.urn
+                        ),
+                    ));
+                }
+            }
+
+            let local_origin = self.by_urn.contains_key(&rel.origin);
+            let local_destination = self.by_urn.contains_key(&rel.destination);
+            let stored_locally = match rel.cardinality.is_foreign_key() {
+                true => local_destination,
+                false => local_origin || local_destination,
+            };
+            if !stored_locally {
+                errors.push(fail(
+                    ErrorCode::InvalidRelation,
+                    &rel.urn,
+                    pointer,
+                    format!(
+                        "relation '{}' would alter tables of imported entities",
+                        rel.urn
+                    ),
+                ));
+            }
+        }
+
+        // Inverse names are distinct among the relations navigated from an entity
         for entity in &self.entities {
-            for uc in &entity.unique_constraints {
-                for urn in &uc.relations {
-                    let relation = self.relations.iter().find(|rel| rel.urn == *urn);
-                    if relation.is_none() {
-                        return Err(Error::new(&format!(
-                            "in entity '{}', in unique constraint, relation '{}' not found",
-                            entity.urn, urn
-                        )));
-                    }
+            let mut names: BTreeSet<&str> = self
+                .relations
+                .iter()
+                .filter(|rel| rel.origin == entity.urn)
+                .map(|rel| rel.name.as_str())
+                .collect();
+            let repeated = self
+                .relations
+                .iter()
+                .filter(|rel| rel.destination == entity.urn)
+                .filter_map(|rel| rel.inverse_name.as_deref().map(|name| (rel, name)))
+                .filter(|(_, name)| !names.insert(name));
+            for (rel, name) in repeated {
+                errors.push(fail(
+                    ErrorCode::Conflict,
+                    &rel.urn,
+                    format!("{}/inverse_name", self.relation_pointer(&rel.urn)),
+                    format!(
+                        "in entity '{}', inverse name '{}' of relation '{}' is already used",
+                        entity.urn, name, rel.urn
+                    ),
+                ));
+            }
+        }
 
-                    let relation = relation.unwrap();
+        // Validate that all relations exist in all entities' UniqueConstraints
+        for (i, entity) in self.entities.iter().enumerate() {
+            for (j, uc) in entity.unique_constraints.iter().enumerate() {
+                for (k, urn) in uc.relations.iter().enumerate() {
+                    let pointer =
+                        format!("/entities/{}/unique_constraints/{}/relations/{}", i, j, k);
+                    let relation = match self.relations.iter().find(|rel| rel.urn == *urn) {
+                        Some(relation) => relation,
+                        None => {
+                            errors.push(fail(
+                                ErrorCode::NotFound,
+                                &entity.urn,
+                                pointer,
+                                format!(
+                                    "in entity '{}', in unique constraint, relation '{}' not found",
+                                    entity.urn, urn
+                                ),
+                            ));
+                            continue;
+                        }
+                    };
                     let is_m2m = relation.cardinality == Cardinality::ManyToMany;
-                    let is_m2o = relation.cardinality == Cardinality::OneToMany
-                        && relation.origin == entity.urn;
-                    if is_m2m || is_m2o {
-                        return Err(Error::new(&format!(
-                            "in entity '{}': unique constraints can only use OneToMany relations with the entity as the destination", entity.urn
-                        )));
+                    let is_origin =
+                        relation.cardinality.is_foreign_key() && relation.origin == entity.urn;
+                    if is_m2m || is_origin {
+                        errors.push(fail(
+                            ErrorCode::InvalidEntity,
+                            &entity.urn,
+                            pointer,
+                            format!("in entity '{}': unique constraints can only use OneToMany or OneToOne relations with the entity as the destination", entity.urn),
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Physical names cannot collide
+        let mut tables: BTreeSet<String> = BTreeSet::new();
+        for name in self
+            .table_names()
+            .into_iter()
+            .chain(
+                self.imports
+                    .iter()
+                    .map(|ent| ent.as_sql_table_name().to_owned()),
+            )
+            .filter(|name| !tables.insert(name.clone()))
+            .filter(|name| {
+                self.entities
+                    .iter()
+                    .chain(self.imports.iter())
+                    .filter(|ent| ent.plural == *name)
+                    .count()
+                    < 2
+            })
+        {
+            errors.push(Error::with_code(
+                ErrorCode::Conflict,
+                &format!("table name '{}' occurs more than once", name),
+            ));
+        }
+
+        for owner in &self.entities {
+            if self.table_owner(owner).urn != owner.urn {
+                continue;
+            }
+            let stored = self.stored_entities(owner);
+            let mut columns: BTreeSet<String> = match owner.extends {
+                Some(_) => vec![sql::BASE_ID],
+                None => vec![sql::BASE_ID, "created_at", "updated_at"],
+            }
+            .into_iter()
+            .chain(Some(entity::DISCRIMINATOR).filter(|_| stored.len() > 1))
+            .map(|name| name.to_string())
+            .collect();
+            for entity in stored {
+                for (j, attr) in entity.attributes.iter().enumerate() {
+                    if !columns.insert(attr.id.clone()) {
+                        errors.push(
+                            Error::with_code(
+                                ErrorCode::Conflict,
+                                &format!(
+                                "in table '{}', column '{}' of entity '{}' occurs more than once",
+                                owner.as_sql_table_name(),
+                                attr.id,
+                                entity.urn
+                            ),
+                            )
+                            .with_urn(&entity.urn)
+                            .with_attribute(&attr.id)
+                            .at(&format!(
+                                "{}/attributes/{}/id",
+                                self.entity_pointer(&entity.urn),
+                                j
+                            )),
+                        );
                     }
                 }
+                for index in self.relations_of_entity(
+                    &entity.urn,
+                    &[Cardinality::OneToMany, Cardinality::OneToOne],
+                ) {
+                    let relation = &self.relations[index];
+                    let key = relation.origin_key();
+                    if !columns.insert(key.clone()) {
+                        errors.push(fail(
+                            ErrorCode::Conflict,
+                            &relation.urn,
+                            self.relation_pointer(&relation.urn),
+                            format!(
+                                "in entity '{}', column '{}' of relation '{}' occurs more than once",
+                                entity.urn, key, relation.urn
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Physical names are only meaningful once the schema can be generated
+        if errors.is_empty() {
+            errors.extend(
+                self.physical_diagnostics()
+                    .into_iter()
+                    .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                    .map(|diagnostic| diagnostic.error),
+            );
+        }
+
+        errors
+    }
+
+    /// Collisions the indexes of the model would otherwise resolve by keeping
+    /// the last entity or relation: repeated urns, singulars and plurals,
+    /// which share a namespace, and repeated ids.
+    fn index_errors(&self) -> Vec<Error> {
+        // Index of the first item with a key, if not the given one
+        fn first<K: Ord>(index: &mut BTreeMap<K, usize>, key: K, i: usize) -> Option<usize> {
+            let first = *index.entry(key).or_insert(i);
+            Some(first).filter(|first| *first != i)
+        }
+
+        let mut errors = Vec::new();
+        let conflict = |urn: &str, pointer: String, msg: String| {
+            Error::with_code(ErrorCode::Conflict, &msg)
+                .with_urn(urn)
+                .at(&pointer)
+        };
+
+        // Imports share the names and ids of the entities they sit beside
+        let entities: Vec<(usize, &Entity)> = self
+            .entities
+            .iter()
+            .enumerate()
+            .chain(
+                self.imports
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ent)| (self.entities.len() + i, ent)),
+            )
+            .collect();
+        let pointer_of = |i: usize, field: &str| match i.checked_sub(self.entities.len()) {
+            None => format!("/entities/{}/{}", i, field),
+            Some(j) => format!("/imports/{}", self.imported_at[j]),
+        };
+
+        let mut urns: BTreeMap<&str, String> = BTreeMap::new();
+        let mut names: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut ids: BTreeMap<u32, usize> = BTreeMap::new();
+        for &(i, ent) in &entities {
+            let pointer = pointer_of(i, "urn");
+            let other = urns.entry(&ent.urn).or_insert_with(|| pointer.clone());
+            if *other != pointer {
+                errors.push(conflict(
+                    &ent.urn,
+                    pointer.clone(),
+                    format!("urn '{}' is already used at '{}'", ent.urn, other),
+                ));
+            }
+            for (field, name) in &[("singular", &ent.singular), ("plural", &ent.plural)] {
+                if let Some(j) = first(&mut names, name.as_str(), i) {
+                    errors.push(conflict(
+                        &ent.urn,
+                        pointer_of(i, field),
+                        format!(
+                            "{} '{}' is already a name of entity '{}'",
+                            field, name, entities[j].1.urn
+                        ),
+                    ));
+                }
+            }
+            if let Some(j) = first(&mut ids, ent.id, i) {
+                errors.push(conflict(
+                    &ent.urn,
+                    pointer_of(i, "id"),
+                    format!(
+                        "id {} is already that of entity '{}'",
+                        ent.id, entities[j].1.urn
+                    ),
+                ));
+            }
+        }
+
+        let mut ids: BTreeMap<u32, usize> = BTreeMap::new();
+        for (i, rel) in self.relations.iter().enumerate() {
+            let pointer = format!("/relations/{}", i);
+            let other = urns.entry(&rel.urn).or_insert_with(|| pointer.clone());
+            if *other != pointer {
+                errors.push(conflict(
+                    &rel.urn,
+                    format!("{}/urn", pointer),
+                    format!("urn '{}' is already used at '{}'", rel.urn, other),
+                ));
+            }
+            if let Some(j) = first(&mut ids, rel.id, i) {
+                errors.push(conflict(
+                    &rel.urn,
+                    format!("{}/id", pointer),
+                    format!(
+                        "id {} is already that of relation '{}'",
+                        rel.id, self.relations[j].urn
+                    ),
+                ));
+            }
+        }
+
+        errors
+    }
+
+    /// Declarations that are valid but have no effect.
+    fn warnings(&self) -> Vec<Error> {
+        let entities = self.entities.iter().enumerate().map(|(i, ent)| {
+            (
+                &ent.urn,
+                format!("/entities/{}", i),
+                &ent.unique_constraints,
+                &ent.attributes,
+            )
+        });
+        let relations = self.relations.iter().enumerate().map(|(i, rel)| {
+            (
+                &rel.urn,
+                format!("/relations/{}", i),
+                &rel.unique_constraints,
+                &rel.attributes,
+            )
+        });
+
+        let mut warnings = Vec::new();
+        for (urn, pointer, constraints, attributes) in entities.chain(relations) {
+            for (j, uc) in constraints.iter().enumerate() {
+                let same = |other: &UniqueConstraint| {
+                    let set = |uc: &UniqueConstraint| -> BTreeSet<String> {
+                        uc.attributes.iter().chain(&uc.relations).cloned().collect()
+                    };
+                    set(other) == set(uc)
+                };
+                let msg = match (uc.attributes.as_slice(), uc.relations.is_empty()) {
+                    _ if constraints[..j].iter().any(same) => {
+                        "unique constraint repeats a previous one".to_owned()
+                    }
+                    ([id], true) if attributes.iter().any(|a| a.id == *id && a.unique) => {
+                        format!("unique constraint is implied by unique attribute '{}'", id)
+                    }
+                    _ => continue,
+                };
+                warnings.push(
+                    Error::with_code(ErrorCode::Redundant, &msg)
+                        .with_urn(urn)
+                        .at(&format!("{}/unique_constraints/{}", pointer, j)),
+                );
             }
         }
+        warnings
+    }
+
+    /// JSON pointer to an entity of the submitted model, or to the urn importing it.
+    fn entity_pointer(&self, urn: &str) -> String {
+        match (self.by_urn.get(urn), self.by_urn_imports.get(urn)) {
+            (Some(index), _) => format!("/entities/{}", index),
+            (None, Some(index)) => format!("/imports/{}", self.imported_at[*index]),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// JSON pointer to a field of an entity of the submitted model, or to
+    /// the urn importing it.
+    fn entity_field_pointer(&self, urn: &str, field: &str) -> String {
+        match self.by_urn.contains_key(urn) {
+            true => format!("{}/{}", self.entity_pointer(urn), field),
+            false => self.entity_pointer(urn),
+        }
+    }
 
-        Ok(())
+    /// JSON pointer to a relation of the submitted model.
+    fn relation_pointer(&self, urn: &str) -> String {
+        self.by_urn_relations
+            .get(urn)
+            .map(|index| format!("/relations/{}", index))
+            .unwrap_or_default()
     }
 
     /// Get name of attribute who is a foreign key of a relation.
@@ -162,11 +949,10 @@ impl Model {
     fn foreign_key_name(&self, relation_urn: &str, destination: bool) -> String {
         let index = *self.by_urn_relations.get(relation_urn).unwrap();
         let relation = &self.relations[index];
-        let (origin, dest) = self.entities_of_relation(relation);
         if destination {
-            format!("{}_id", dest.singular)
+            relation.destination_key()
         } else {
-            format!("{}_id", origin.singular)
+            relation.origin_key()
         }
     }
 
@@ -174,7 +960,7 @@ impl Model {
         // Convert each unique constraint field to a column name
         let attributes: Vec<&str> = uc.attributes.iter().map(|s| s.as_str()).collect();
 
-        // Consider only OneToMany relations
+        // Consider only OneToMany and OneToOne relations
         let relations: Vec<String> = uc
             .relations
             .iter()
@@ -188,192 +974,673 @@ impl Model {
             .collect()
     }
 
-    /// List the unique attributes of a table.
-    /// Returns the attributes that are either flagged as unique
-    /// or that participate in a unique constraint.
+    /// List the unique attributes of a table, each once.
+    /// Returns the attributes that are either flagged as unique,
+    /// that participate in a unique constraint or that are foreign keys
+    /// of OneToOne relations. For relation tables, the pair of related
+    /// keys comes first.
     pub fn table_unique_attributes(&self, name: &str) -> Option<Vec<String>> {
         let opt_index = self.by_plural.get(name);
         match opt_index {
-            None => None,
-            Some(index) => Some(
-                self.entities[*index]
-                    .unique_constraints
-                    .iter()
-                    .flat_map(|uc| self.unique_constraint_attributes(uc))
-                    .chain(
-                        self.entities[*index]
-                            .attributes
-                            .iter()
-                            .filter(|attr| attr.unique)
-                            .map(|attr| attr.id.clone()),
+            None => self
+                .relations
+                .iter()
+                .filter(|rel| rel.cardinality == Cardinality::ManyToMany)
+                .find(|rel| self.relation_table_name(rel) == name)
+                .map(|rel| {
+                    let mut keys = vec![rel.origin_key(), rel.destination_key()];
+                    if rel.ordered {
+                        keys.push(relation::POSITION.to_owned());
+                    }
+                    without_repeats(
+                        keys.into_iter()
+                            .chain(
+                                rel.unique_constraints
+                                    .iter()
+                                    .flat_map(|uc| uc.attributes.iter().cloned()),
+                            )
+                            .chain(
+                                rel.attributes
+                                    .iter()
+                                    .filter(|attr| attr.unique)
+                                    .map(|attr| attr.id.clone()),
+                            )
+                            .collect(),
                     )
-                    .collect(),
-            ),
+                }),
+            Some(index) => {
+                // Subtypes of a single table hierarchy have no table of their own
+                let entity = &self.entities[*index];
+                if self.table_owner(entity).urn != entity.urn {
+                    return None;
+                }
+                Some(without_repeats(
+                    self.stored_entities(entity)
+                        .into_iter()
+                        .flat_map(|ent| {
+                            ent.unique_constraints
+                                .iter()
+                                .flat_map(|uc| self.unique_constraint_attributes(uc))
+                                .chain(
+                                    ent.attributes
+                                        .iter()
+                                        .filter(|attr| attr.unique)
+                                        .map(|attr| attr.id.clone()),
+                                )
+                                .chain(
+                                    self.relations_of_entity(&ent.urn, &[Cardinality::OneToOne])
+                                        .into_iter()
+                                        .map(|i| {
+                                            self.foreign_key_name(&self.relations[i].urn, false)
+                                        }),
+                                )
+                                .collect::<Vec<String>>()
+                        })
+                        .collect(),
+                ))
+            }
         }
     }
 
-    fn entity_as_sql(&self, entity_ref: &Entity) -> Option<Vec<String>> {
-        let mut table = entity_ref.as_sql_table();
-        let trigger = entity_ref.as_sql_trigger();
+    /// Table of an entity along with the trigger keeping its timestamps.
+    fn entity_as_sql_table(
+        &self,
+        entity_ref: &Entity,
+    ) -> Option<(sql::Table, Option<sql::Trigger>)> {
+        // Subtypes of a single table hierarchy are stored in the table of the root
+        if self.table_owner(entity_ref).urn != entity_ref.urn {
+            return None;
+        }
+
+        let parent = self.ancestors(entity_ref).first().copied();
+        let mut table = match parent {
+            Some(parent) => entity_ref.as_sql_subtype_table(parent),
+            None => entity_ref.as_sql_table(),
+        };
+
+        // Columns of subtypes sharing the table are only required on their rows
+        let stored = self.stored_entities(entity_ref);
+        if stored.len() > 1 {
+            let values: Vec<String> = self
+                .discriminator_values(entity_ref)
+                .iter()
+                .map(|value| format!("'{}'", value))
+                .collect();
+            table.columns.push(sql::Column {
+                name: entity::DISCRIMINATOR.to_owned(),
+                ty: sql::Type::Text,
+                default: Some(format!("'{}'", entity_ref.singular)),
+                not_null: true,
+                checks: vec![format!(
+                    "{} IN ({})",
+                    entity::DISCRIMINATOR,
+                    values.join(", ")
+                )],
+                ..Default::default()
+            });
+        }
+        for subtype in stored.iter().skip(1) {
+            for attr in &subtype.attributes {
+                let mut column: sql::Column = attr.clone().into();
+                if column.not_null {
+                    column.not_null = false;
+                    column
+                        .checks
+                        .push(self.discriminator_check(subtype, &attr.id));
+                }
+                table.columns.push(column);
+            }
+        }
 
         // Push unique constraints
-        entity_ref.unique_constraints.iter().for_each(|uc| {
-            table.unique_constraints.push(sql::UniqueConstraint::new(
-                self.unique_constraint_attributes(uc),
-            ));
-        });
+        stored
+            .iter()
+            .flat_map(|ent| ent.unique_constraints.iter())
+            .for_each(|uc| {
+                table.unique_constraints.push(sql::UniqueConstraint::new(
+                    self.unique_constraint_attributes(uc),
+                ));
+            });
 
         // Entity as origin of OneToMany does not impact SQL table.
         // Entity as origin/destination of ManyToMany does not impact SQL table.
 
-        // Entity as destination of OneToMany does impact.
+        // Entity as destination of OneToMany or OneToOne does impact.
         // The check must be part of validate() method.
-        let related = self.relations_of_entity(&entity_ref.urn, Cardinality::OneToMany);
+        for (position, stored_entity) in stored.iter().enumerate() {
+            let related = self.relations_of_entity(
+                &stored_entity.urn,
+                &[Cardinality::OneToMany, Cardinality::OneToOne],
+            );
 
-        if related.is_empty() {
-            return Some(vec![table.to_string(), trigger.to_string()]);
+            for index in related {
+                let relation = &self.relations[index];
+                // Safe to unwrap since validation is assumed.
+                let (origin_entity, _) = self.entities_of_relation(relation);
+                let column_name = relation.origin_key();
+
+                let mut column = sql::Column {
+                    name: column_name.clone(),
+                    ty: origin_entity.identity().as_sql_type(),
+                    not_null: relation.required,
+                    unique: relation.cardinality == Cardinality::OneToOne,
+                    ..Default::default()
+                };
+                if position > 0 && relation.required {
+                    column.not_null = false;
+                    column
+                        .checks
+                        .push(self.discriminator_check(stored_entity, &column_name));
+                }
+                table.columns.push(column);
+
+                table.foreign_keys.push(sql::ForeignKey {
+                    key: column_name,
+                    table_name: self
+                        .table_owner(origin_entity)
+                        .as_sql_table_name()
+                        .to_owned(),
+                    table_key: sql::BASE_ID.to_owned(),
+                    on_delete: Some(relation.delete_action()),
+                    on_update: relation.on_update,
+                });
+            }
         }
 
-        for index in related {
-            let relation = &self.relations[index];
-            // Safe to unwrap since validation is assumed.
-            let origin_index = *self.by_urn.get(&relation.origin).unwrap();
-            let origin_entity = &self.entities[origin_index];
-            let column_name = format!("{}_id", origin_entity.singular);
-
-            table.columns.push(sql::Column {
-                name: column_name.clone(),
-                ty: sql::Type::Integer,
-                not_null: true,
-                ..Default::default()
-            });
-
-            table.foreign_keys.push(sql::ForeignKey {
-                key: column_name,
-                table_name: origin_entity.plural.clone(),
-                table_key: sql::BASE_ID.to_owned(),
-                on_delete_cascade: true,
-            });
+        // The timestamps of subtypes with tables of their own are those of the root
+        match parent {
+            Some(_) => Some((table, None)),
+            None => Some((table, Some(entity_ref.as_sql_trigger()))),
         }
+    }
 
-        Some(vec![table.to_string(), trigger.to_string()])
+    /// Get an entity of the model or an imported one.
+    fn entity(&self, urn: &str) -> Option<&Entity> {
+        match self.by_urn.get(urn) {
+            Some(index) => Some(&self.entities[*index]),
+            None => self.by_urn_imports.get(urn).map(|i| &self.imports[*i]),
+        }
     }
 
+    /// Get the origin and destination of a relation.
+    ///
+    /// # Panics
+    /// Panics if either is not found, validation is assumed.
     fn entities_of_relation(&self, relation_ref: &Relation) -> (&Entity, &Entity) {
         (
-            &self.entities[*self.by_urn.get(&relation_ref.origin).unwrap()],
-            &self.entities[*self.by_urn.get(&relation_ref.destination).unwrap()],
+            self.entity(&relation_ref.origin).unwrap(),
+            self.entity(&relation_ref.destination).unwrap(),
         )
     }
 
-    fn relation_table_name(&self, origin: &Entity, destination: &Entity) -> String {
+    /// Name of the table of a ManyToMany relation, `<origin singular>_<destination plural>`
+    /// or the relation's basename when qualified.
+    fn relation_table_name(&self, relation_ref: &Relation) -> String {
+        if relation_ref.qualified {
+            return urn::basename(&relation_ref.urn).to_owned();
+        }
+        let (origin, destination) = self.entities_of_relation(relation_ref);
         format!("{}_{}", origin.singular, destination.plural)
     }
 
-    fn relation_as_sql(&self, relation_ref: &Relation) -> Option<Vec<String>> {
-        // Nothing to be done for OneToMany relations
-        if relation_ref.cardinality == Cardinality::OneToMany {
+    fn relation_as_sql_table(&self, relation_ref: &Relation) -> Option<sql::Table> {
+        // Nothing to be done for OneToMany and OneToOne relations
+        if relation_ref.cardinality.is_foreign_key() {
             return None;
         }
 
         let (origin, destination) = self.entities_of_relation(relation_ref);
 
-        let origin_key = format!("{}_id", origin.singular);
-        let destination_key = format!("{}_id", destination.singular);
+        let origin_key = relation_ref.origin_key();
+        let destination_key = relation_ref.destination_key();
 
         // Create table with base columns
         let mut table = sql::Table {
-            name: self.relation_table_name(origin, destination),
+            name: self.relation_table_name(relation_ref),
+            primary_key: vec![],
             columns: vec![
                 sql::Column {
                     name: origin_key.clone(),
-                    ty: sql::Type::Integer,
+                    ty: origin.identity().as_sql_type(),
                     not_null: true,
                     ..Default::default()
                 },
                 sql::Column {
                     name: destination_key.clone(),
-                    ty: sql::Type::Integer,
+                    ty: destination.identity().as_sql_type(),
                     not_null: true,
                     ..Default::default()
                 },
             ],
             foreign_keys: vec![
                 sql::ForeignKey {
-                    key: origin_key,
-                    table_name: origin.plural.to_owned(),
+                    key: origin_key.clone(),
+                    table_name: self.table_owner(origin).as_sql_table_name().to_owned(),
                     table_key: sql::BASE_ID.to_owned(),
-                    on_delete_cascade: true,
+                    on_delete: Some(relation_ref.delete_action()),
+                    on_update: relation_ref.on_update,
                 },
                 sql::ForeignKey {
-                    key: destination_key,
-                    table_name: destination.plural.to_owned(),
+                    key: destination_key.clone(),
+                    table_name: self.table_owner(destination).as_sql_table_name().to_owned(),
                     table_key: sql::BASE_ID.to_owned(),
-                    on_delete_cascade: true,
+                    on_delete: Some(relation_ref.delete_action()),
+                    on_update: relation_ref.on_update,
                 },
             ],
-            unique_constraints: vec![],
+            unique_constraints: relation_ref
+                .unique_constraints
+                .iter()
+                .map(|uc| sql::UniqueConstraint::new(uc.attributes.clone()))
+                .collect(),
         };
 
+        // Positions are unique among the links of an origin
+        if relation_ref.ordered {
+            table.columns.push(sql::Column {
+                name: relation::POSITION.to_owned(),
+                ty: sql::Type::Integer,
+                not_null: true,
+                ..Default::default()
+            });
+            table.unique_constraints.insert(
+                0,
+                sql::UniqueConstraint::new(vec![origin_key.clone(), relation::POSITION.to_owned()]),
+            );
+        }
+
+        // Key the table by the related pair, or by an id of its own
+        let pair = vec![origin_key, destination_key];
+        match relation_ref.join_key {
+            JoinKey::Composite => table.primary_key = pair,
+            JoinKey::Surrogate => {
+                table.columns.insert(0, self.identity.as_sql_column());
+                table
+                    .unique_constraints
+                    .insert(0, sql::UniqueConstraint::new(pair));
+            }
+        }
+
         // Add attributes to table
         relation_ref
             .attributes
             .iter()
             .for_each(|attr| table.columns.push(attr.clone().into()));
 
-        Some(vec![table.to_string()])
+        Some(table)
     }
 
-    pub fn get_singular(&self, text: &str) -> Result<&str> {
-        let index = self
-            .by_plural
-            .get(text)
-            .or(self.by_singular.get(text))
-            .ok_or(Error::new(&format!("plural of '{}' not found", text)))?;
-        Ok(&self.entities[*index].singular)
-    }
+    /// Tables, columns and triggers of the generated schema, each with the
+    /// value of the spec it derives from.
+    fn physical_identifiers(&self) -> Vec<physical::Identifier> {
+        let mut identifiers = Vec::new();
+        let mut push = |kind, name: &str, table: Option<&str>, urn: &str, pointer, prefix: &str| {
+            identifiers.push(physical::Identifier {
+                kind,
+                name: name.to_owned(),
+                table: table.map(|t| t.to_owned()),
+                urn: urn.to_owned(),
+                pointer,
+                prefix: prefix.to_owned(),
+            })
+        };
 
-    pub fn get_plural(&self, text: &str) -> Result<&str> {
-        let index = self
-            .by_singular
-            .get(text)
-            .or(self.by_plural.get(text))
-            .ok_or(Error::new(&format!("singular of '{}' not found", text)))?;
+        for entity_ref in &self.entities {
+            let (table, trigger) = match self.entity_as_sql_table(entity_ref) {
+                Some(generated) => generated,
+                None => continue,
+            };
+            let pointer = self.entity_pointer(&entity_ref.urn);
+            let plural = format!("{}/plural", pointer);
+            push(
+                physical::Kind::Table,
+                &table.name,
+                None,
+                &entity_ref.urn,
+                plural.clone(),
+                &self.tenant,
+            );
+
+            let stored = self.stored_entities(entity_ref);
+            for column in &table.columns {
+                let attribute = stored.iter().find_map(|ent| {
+                    let j = ent.attributes.iter().position(|a| a.id == column.name)?;
+                    let pointer = format!("{}/attributes/{}/id", self.entity_pointer(&ent.urn), j);
+                    Some((&ent.urn, pointer, ent.singular.as_str()))
+                });
+                let foreign_key = || {
+                    stored
+                        .iter()
+                        .flat_map(|ent| {
+                            self.relations_of_entity(
+                                &ent.urn,
+                                &[Cardinality::OneToMany, Cardinality::OneToOne],
+                            )
+                        })
+                        .map(|i| &self.relations[i])
+                        .find(|rel| rel.origin_key() == column.name)
+                        .map(|rel| {
+                            (
+                                &rel.urn,
+                                self.relation_pointer(&rel.urn),
+                                urn::basename(&rel.urn),
+                            )
+                        })
+                };
+                let (urn, pointer, prefix) = attribute.or_else(foreign_key).unwrap_or((
+                    &entity_ref.urn,
+                    pointer.clone(),
+                    &entity_ref.singular,
+                ));
+                push(
+                    physical::Kind::Column,
+                    &column.name,
+                    Some(&table.name),
+                    urn,
+                    pointer,
+                    prefix,
+                );
+            }
+
+            if let Some(trigger) = trigger {
+                push(
+                    physical::Kind::Trigger,
+                    &trigger.name(),
+                    None,
+                    &entity_ref.urn,
+                    plural,
+                    &self.tenant,
+                );
+            }
+        }
+
+        for relation_ref in &self.relations {
+            let table = match self.relation_as_sql_table(relation_ref) {
+                Some(table) => table,
+                None => continue,
+            };
+            let pointer = self.relation_pointer(&relation_ref.urn);
+            let basename = urn::basename(&relation_ref.urn);
+            push(
+                physical::Kind::Table,
+                &table.name,
+                None,
+                &relation_ref.urn,
+                pointer.clone(),
+                &self.tenant,
+            );
+            for column in &table.columns {
+                let pointer = match relation_ref
+                    .attributes
+                    .iter()
+                    .position(|a| a.id == column.name)
+                {
+                    Some(j) => format!("{}/attributes/{}/id", pointer, j),
+                    None => pointer.clone(),
+                };
+                push(
+                    physical::Kind::Column,
+                    &column.name,
+                    Some(&table.name),
+                    &relation_ref.urn,
+                    pointer,
+                    basename,
+                );
+            }
+        }
+
+        identifiers
+    }
+
+    /// Collisions and reserved words among the names of the generated schema,
+    /// as used by SQLite and by GraphQL types and fields named after it.
+    fn physical_diagnostics(&self) -> Vec<Diagnostic> {
+        physical::check(
+            &self.physical_identifiers(),
+            &[physical::Target::Sqlite, physical::Target::GraphQL],
+        )
+    }
+
+    /// Generate the json-schema of an entity, including the foreign keys
+    /// of the OneToMany and OneToOne relations it is the destination of.
+    /// Schemas of subtypes are composed with that of their parent under `allOf`.
+    pub fn entity_json_schema(&self, urn: &str) -> Result<JsonSchema> {
+        let index = self.by_urn.get(urn).ok_or_else(|| {
+            Error::with_code(ErrorCode::NotFound, &format!("entity '{}' not found", urn))
+        })?;
+        let entity = &self.entities[*index];
+
+        use crate::json_schema::GenerateSchema;
+        let mut schema = entity.json_schema()?;
+        for index in self.relations_of_entity(
+            &entity.urn,
+            &[Cardinality::OneToMany, Cardinality::OneToOne],
+        ) {
+            let relation = &self.relations[index];
+            let (origin, _) = self.entities_of_relation(relation);
+            schema.add_property(
+                &self.foreign_key_name(&relation.urn, false),
+                origin.identity().as_json_schema_type(),
+                relation.required,
+            )?;
+        }
+
+        // Rows of a single table hierarchy tell which entity they belong to
+        let root = self.root(entity);
+        if self.inheritance(entity) == Inheritance::SingleTable
+            && self.stored_entities(root).len() > 1
+        {
+            let values = self
+                .discriminator_values(entity)
+                .into_iter()
+                .map(serde_json::Value::String)
+                .collect();
+            let ty = json_schema::Type::new_from_str("string")
+                .with_enum(values)
+                .with_default(Some(serde_json::Value::String(entity.singular.clone())));
+            schema.add_property(entity::DISCRIMINATOR, ty, entity.extends.is_some())?;
+        }
+
+        if let Some(parent) = self.ancestors(entity).first() {
+            schema.add_all_of(self.entity_json_schema(&parent.urn)?);
+        }
+        Ok(schema)
+    }
+
+    /// Generate the json-schema of a relation, with foreign keys typed
+    /// after the identity strategy of the related entities.
+    pub fn relation_json_schema(&self, urn: &str) -> Result<JsonSchema> {
+        let index = self.by_urn_relations.get(urn).ok_or_else(|| {
+            Error::with_code(
+                ErrorCode::NotFound,
+                &format!("relation '{}' not found", urn),
+            )
+        })?;
+        let relation = &self.relations[*index];
+        let (origin, destination) = self.entities_of_relation(relation);
+        relation.json_schema_with_ids(origin.identity(), destination.identity())
+    }
+
+    pub fn get_singular(&self, text: &str) -> Result<&str> {
+        let index = self
+            .by_plural
+            .get(text)
+            .or(self.by_singular.get(text))
+            .ok_or_else(|| {
+                Error::with_code(
+                    ErrorCode::NotFound,
+                    &format!("plural of '{}' not found", text),
+                )
+            })?;
+        Ok(&self.entities[*index].singular)
+    }
+
+    pub fn get_plural(&self, text: &str) -> Result<&str> {
+        let index = self
+            .by_singular
+            .get(text)
+            .or(self.by_plural.get(text))
+            .ok_or_else(|| {
+                Error::with_code(
+                    ErrorCode::NotFound,
+                    &format!("singular of '{}' not found", text),
+                )
+            })?;
         Ok(&self.entities[*index].plural)
     }
 
     pub fn is_relation_table(&self, name: &str) -> bool {
         self.relations
             .iter()
-            .map(|rel| self.entities_of_relation(rel))
-            .any(|(a, b)| self.relation_table_name(a, b) == name)
+            .filter(|rel| rel.cardinality == Cardinality::ManyToMany)
+            .any(|rel| self.relation_table_name(rel) == name)
+    }
+
+    /// Relations of an entity with their name read from it, forward ones first.
+    fn navigations_of(&self, urn: &str) -> Vec<(&Relation, &str, Direction)> {
+        let forward = self
+            .relations
+            .iter()
+            .filter(|rel| rel.origin == urn)
+            .map(|rel| (rel, rel.name.as_str(), Direction::Forward));
+        let inverse = self
+            .relations
+            .iter()
+            .filter(|rel| rel.destination == urn)
+            .map(|rel| (rel, rel.inverse_name(), Direction::Inverse));
+        forward.chain(inverse).collect()
+    }
+
+    /// List the relations of an entity as navigated from it, with names and
+    /// descriptions of the inverse direction for relations it is the destination of.
+    pub fn navigation(&self, urn: &str) -> Result<Vec<Navigation>> {
+        if !self.by_urn.contains_key(urn) {
+            return Err(Error::with_code(
+                ErrorCode::NotFound,
+                &format!("entity '{}' not found", urn),
+            ));
+        }
+
+        Ok(self
+            .navigations_of(urn)
+            .into_iter()
+            .map(|(rel, name, direction)| {
+                let (description, target) = match direction {
+                    Direction::Forward => (rel.description.clone(), rel.destination.clone()),
+                    Direction::Inverse => (
+                        rel.inverse_description
+                            .clone()
+                            .unwrap_or_else(|| rel.description.clone()),
+                        rel.origin.clone(),
+                    ),
+                };
+                Navigation {
+                    relation: rel.urn.clone(),
+                    direction,
+                    name: name.to_owned(),
+                    description,
+                    target,
+                    cardinality: rel.cardinality,
+                }
+            })
+            .collect())
+    }
+
+    /// Statements keeping the positions of an ordered relation dense.
+    pub fn reorder_sql(&self, urn: &str) -> Result<sql::ReorderStatements> {
+        let index = self.by_urn_relations.get(urn).ok_or_else(|| {
+            Error::with_code(
+                ErrorCode::NotFound,
+                &format!("relation '{}' not found", urn),
+            )
+        })?;
+        let relation = &self.relations[*index];
+        if !relation.ordered {
+            return Err(Error::with_code(
+                ErrorCode::InvalidRelation,
+                &format!("relation '{}' is not ordered", urn),
+            )
+            .with_urn(urn));
+        }
+        Ok(sql::ReorderStatements::new(
+            &self.relation_table_name(relation),
+            &relation.origin_key(),
+            &relation.destination_key(),
+            relation::POSITION,
+        ))
+    }
+
+    /// Physical names of each relation, for clients addressing them
+    /// through the generated tables.
+    pub fn relation_mappings(&self) -> Vec<RelationMapping> {
+        self.relations
+            .iter()
+            .map(|rel| {
+                let (_, destination) = self.entities_of_relation(rel);
+                match rel.cardinality.is_foreign_key() {
+                    true => RelationMapping {
+                        urn: rel.urn.clone(),
+                        table: self.table_owner(destination).as_sql_table_name().to_owned(),
+                        origin_key: rel.origin_key(),
+                        destination_key: sql::BASE_ID.to_owned(),
+                    },
+                    false => RelationMapping {
+                        urn: rel.urn.clone(),
+                        table: self.relation_table_name(rel),
+                        origin_key: rel.origin_key(),
+                        destination_key: rel.destination_key(),
+                    },
+                }
+            })
+            .collect()
     }
 
+    /// Names of the tables of the model, but for join tables of relations
+    /// missing an entity.
     pub fn table_names(&self) -> Vec<String> {
         let relation_tables = self
             .relations
             .iter()
             .filter(|rel| rel.cardinality == Cardinality::ManyToMany)
-            .map(|rel| self.entities_of_relation(rel))
-            .map(|(orig, dest)| self.relation_table_name(orig, dest));
+            .filter(|rel| {
+                self.entity(&rel.origin).is_some() && self.entity(&rel.destination).is_some()
+            })
+            .map(|rel| self.relation_table_name(rel));
 
         self.entities
             .iter()
+            .filter(|ent| self.table_owner(ent).urn == ent.urn)
             .map(|ent| ent.as_sql_table_name().to_owned())
             .chain(relation_tables)
             .collect()
     }
 }
 
+/// Drop repeated names, keeping the first occurrence of each.
+fn without_repeats(names: Vec<String>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    names
+        .into_iter()
+        .filter(|name| seen.insert(name.clone()))
+        .collect()
+}
+
 impl sql::GenerateSql for Model {
     fn generate_sql(&self) -> Vec<String> {
         self.entities
             .iter()
-            .map(|ent| self.entity_as_sql(ent))
-            .chain(self.relations.iter().map(|rel| self.relation_as_sql(rel)))
-            .filter(|opt| opt.is_some())
-            .map(|opt| opt.unwrap())
-            .flatten()
+            .filter_map(|ent| self.entity_as_sql_table(ent))
+            .flat_map(|(table, trigger)| {
+                Some(table.to_string())
+                    .into_iter()
+                    .chain(trigger.map(|trigger| trigger.to_string()))
+            })
+            .chain(
+                self.relations
+                    .iter()
+                    .filter_map(|rel| self.relation_as_sql_table(rel))
+                    .map(|table| table.to_string()),
+            )
             .collect()
     }
 }
@@ -382,6 +1649,17 @@ impl sql::GenerateSql for Model {
 mod tests {
     use super::*;
 
+    fn uuid_serde_model() -> SerdeModel {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.identity = Identity::Uuid;
+        smodel.entities.push(part_entity());
+        smodel.entities.push(category_entity());
+        smodel.entities.push(price_entity());
+        smodel.relations.push(categorised_relation());
+        smodel.relations.push(pricedby_relation());
+        smodel
+    }
+
     fn empty_serde_model() -> Result<SerdeModel> {
         let raw = r#"{
             "tenant": "sampleperry",
@@ -681,7 +1959,7 @@ mod tests {
         let smodel: SerdeModel = serde_json::from_slice(raw.as_bytes()).unwrap();
         let model = Model::new(smodel).unwrap();
         use crate::sql::GenerateSql;
-        assert_eq!(model.generate_sql().join("\n"), "CREATE TABLE parts(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, label TEXT UNIQUE NOT NULL, description TEXT, manufacturer TEXT, barcode TEXT);\nCREATE TRIGGER parts_updated_at AFTER UPDATE ON parts WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE parts SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE categories(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, name TEXT UNIQUE NOT NULL, description TEXT);\nCREATE TRIGGER categories_updated_at AFTER UPDATE ON categories WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE categories SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE prices(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, value REAL NOT NULL, part_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE);\nCREATE TRIGGER prices_updated_at AFTER UPDATE ON prices WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE prices SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE storage_sites(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, name TEXT UNIQUE NOT NULL, contact_person TEXT NOT NULL, phone_number TEXT NOT NULL, email_address TEXT NOT NULL);\nCREATE TRIGGER storage_sites_updated_at AFTER UPDATE ON storage_sites WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE storage_sites SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE storage_areas(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, name TEXT UNIQUE NOT NULL, storage_site_id INTEGER NOT NULL, FOREIGN KEY (storage_site_id) REFERENCES storage_sites (id) ON DELETE CASCADE);\nCREATE TRIGGER storage_areas_updated_at AFTER UPDATE ON storage_areas WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE storage_areas SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE stock_item_configs(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, minimum INTEGER, maximum INTEGER, replenishment INTEGER, part_id INTEGER NOT NULL, storage_site_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (storage_site_id) REFERENCES storage_sites (id) ON DELETE CASCADE, UNIQUE (storage_site_id, part_id));\nCREATE TRIGGER stock_item_configs_updated_at AFTER UPDATE ON stock_item_configs WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE stock_item_configs SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE stock_entries(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, quantity INTEGER, part_id INTEGER NOT NULL, storage_area_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (storage_area_id) REFERENCES storage_areas (id) ON DELETE CASCADE, UNIQUE (storage_area_id, part_id));\nCREATE TRIGGER stock_entries_updated_at AFTER UPDATE ON stock_entries WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE stock_entries SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE part_categories(part_id INTEGER NOT NULL, category_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE);");
+        assert_eq!(model.generate_sql().join("\n"), "CREATE TABLE parts(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, label TEXT UNIQUE NOT NULL, description TEXT, manufacturer TEXT, barcode TEXT);\nCREATE TRIGGER parts_updated_at AFTER UPDATE ON parts WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE parts SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE categories(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, name TEXT UNIQUE NOT NULL, description TEXT);\nCREATE TRIGGER categories_updated_at AFTER UPDATE ON categories WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE categories SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE prices(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, value REAL NOT NULL, part_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE);\nCREATE TRIGGER prices_updated_at AFTER UPDATE ON prices WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE prices SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE storage_sites(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, name TEXT UNIQUE NOT NULL, contact_person TEXT NOT NULL, phone_number TEXT NOT NULL, email_address TEXT NOT NULL);\nCREATE TRIGGER storage_sites_updated_at AFTER UPDATE ON storage_sites WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE storage_sites SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE storage_areas(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, name TEXT UNIQUE NOT NULL, storage_site_id INTEGER NOT NULL, FOREIGN KEY (storage_site_id) REFERENCES storage_sites (id) ON DELETE CASCADE);\nCREATE TRIGGER storage_areas_updated_at AFTER UPDATE ON storage_areas WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE storage_areas SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE stock_item_configs(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, minimum INTEGER, maximum INTEGER, replenishment INTEGER, part_id INTEGER NOT NULL, storage_site_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (storage_site_id) REFERENCES storage_sites (id) ON DELETE CASCADE, UNIQUE (storage_site_id, part_id));\nCREATE TRIGGER stock_item_configs_updated_at AFTER UPDATE ON stock_item_configs WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE stock_item_configs SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE stock_entries(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, quantity INTEGER, part_id INTEGER NOT NULL, storage_area_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (storage_area_id) REFERENCES storage_areas (id) ON DELETE CASCADE, UNIQUE (storage_area_id, part_id));\nCREATE TRIGGER stock_entries_updated_at AFTER UPDATE ON stock_entries WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE stock_entries SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE part_categories(part_id INTEGER NOT NULL, category_id INTEGER NOT NULL, PRIMARY KEY (part_id, category_id), FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE);");
         assert_eq!(
             model.table_unique_attributes("parts"),
             Some(vec!["label".into()])
@@ -949,8 +2227,874 @@ mod tests {
         use crate::sql::GenerateSql;
         assert_eq!(
             model.generate_sql().join("\n"),
-            "CREATE TABLE parts(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, label TEXT UNIQUE NOT NULL, stock INTEGER);\nCREATE TRIGGER parts_updated_at AFTER UPDATE ON parts WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE parts SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE categories(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, name TEXT UNIQUE NOT NULL);\nCREATE TRIGGER categories_updated_at AFTER UPDATE ON categories WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE categories SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE prices(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, value REAL NOT NULL, part_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE);\nCREATE TRIGGER prices_updated_at AFTER UPDATE ON prices WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE prices SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE prices_seller(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, value REAL NOT NULL, seller TEXT NOT NULL, part_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, UNIQUE (seller, part_id));\nCREATE TRIGGER prices_seller_updated_at AFTER UPDATE ON prices_seller WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE prices_seller SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE part_categories(part_id INTEGER NOT NULL, category_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE);"
+            "CREATE TABLE parts(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, label TEXT UNIQUE NOT NULL, stock INTEGER);\nCREATE TRIGGER parts_updated_at AFTER UPDATE ON parts WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE parts SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE categories(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, name TEXT UNIQUE NOT NULL);\nCREATE TRIGGER categories_updated_at AFTER UPDATE ON categories WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE categories SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE prices(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, value REAL NOT NULL, part_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE);\nCREATE TRIGGER prices_updated_at AFTER UPDATE ON prices WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE prices SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE prices_seller(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL, value REAL NOT NULL, seller TEXT NOT NULL, part_id INTEGER NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, UNIQUE (seller, part_id));\nCREATE TRIGGER prices_seller_updated_at AFTER UPDATE ON prices_seller WHEN old.updated_at < CURRENT_TIMESTAMP BEGIN UPDATE prices_seller SET updated_at = CURRENT_TIMESTAMP WHERE id = old.id; END;\nCREATE TABLE part_categories(part_id INTEGER NOT NULL, category_id INTEGER NOT NULL, PRIMARY KEY (part_id, category_id), FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE);"
+        )
+    }
+
+    fn datasheet_entity() -> Entity {
+        serde_json::from_str(
+            r#"{
+            "id": 9,
+            "urn": "sampleperry:datasheet",
+            "singular": "datasheet",
+            "plural": "datasheets",
+            "name": "Datasheet",
+            "description": "A part's datasheet",
+            "visibility": "Tenant",
+            "attributes": [
+                {
+                    "id": "url",
+                    "name": "URL",
+                    "required": true,
+                    "type": "string"
+                }
+            ]
+        }"#,
         )
+        .unwrap()
+    }
+
+    fn documentedby_relation() -> Relation {
+        serde_json::from_str(
+            r#"{
+            "id": 10,
+            "urn": "sampleperry:documentedby",
+            "name": "Documented By",
+            "description": "Datasheet of a part",
+            "visibility": "Tenant",
+            "origin": "sampleperry:part",
+            "destination": "sampleperry:datasheet",
+            "cardinality": "OneToOne",
+            "attributes": []
+        }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn model_one_to_one() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(datasheet_entity());
+        smodel.relations.push(documentedby_relation());
+        smodel.entities[1]
+            .unique_constraints
+            .push(UniqueConstraint {
+                attributes: vec!["url".into()],
+                relations: vec!["sampleperry:documentedby".into()],
+            });
+        let model = Model::new(smodel.clone()).expect("model with OneToOne should be valid");
+
+        use crate::sql::GenerateSql;
+        let sql = model.generate_sql();
+        assert_eq!(sql.len(), 4);
+        assert!(sql[2].ends_with(
+            "url TEXT NOT NULL, part_id INTEGER UNIQUE NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, UNIQUE (url, part_id));"
+        ));
+        assert_eq!(
+            model.table_unique_attributes("datasheets"),
+            Some(vec!["url".into(), "part_id".into()])
+        );
+        assert_eq!(model.table_names(), vec!["parts", "datasheets"]);
+        assert!(!model.is_relation_table("part_datasheets"));
+
+        let mut invalid = smodel.clone();
+        invalid.entities[1].unique_constraints.clear();
+        invalid.entities[0]
+            .unique_constraints
+            .push(UniqueConstraint {
+                attributes: vec![],
+                relations: vec!["sampleperry:documentedby".into()],
+            });
+        Model::new(invalid).expect_err("origin of OneToOne cannot use it as unique");
+
+        let mut invalid = smodel;
+        invalid.relations[0].attributes = part_entity().attributes;
+        Model::new(invalid).expect_err("OneToOne relations cannot have attributes");
+    }
+
+    #[test]
+    fn model_optional_relation() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(category_entity());
+        smodel.entities.push(price_entity());
+        smodel.relations.push(pricedby_relation());
+        smodel.relations.push(hasprice_relation());
+        smodel.relations[1].required = false;
+        let model = Model::new(smodel.clone()).expect("optional relation should be valid");
+
+        use crate::sql::GenerateSql;
+        assert!(model.generate_sql()[4].ends_with(
+            "value REAL NOT NULL, part_id INTEGER NOT NULL, category_id INTEGER, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE);"
+        ));
+
+        let schema = model.entity_json_schema("sampleperry:price").unwrap();
+        let schema = serde_json::to_value(schema).unwrap();
+        assert_eq!(schema["required"], serde_json::json!(["value", "part_id"]));
+        assert_eq!(
+            schema["properties"]["category_id"],
+            serde_json::json!({ "type": ["integer", "null"] })
+        );
+        assert!(model.entity_json_schema("sampleperry:foo").is_err());
+
+        let mut invalid = smodel;
+        invalid.relations.push(categorised_relation());
+        invalid.relations[2].required = false;
+        Model::new(invalid).expect_err("ManyToMany relations cannot be optional");
+    }
+
+    #[test]
+    fn model_referential_actions() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(category_entity());
+        smodel.entities.push(price_entity());
+        smodel.relations.push(categorised_relation());
+        smodel.relations.push(hasprice_relation());
+        smodel.relations[0].on_delete = Some(sql::ReferentialAction::Restrict);
+        smodel.relations[1].required = false;
+        smodel.relations[1].on_delete = Some(sql::ReferentialAction::SetNull);
+        smodel.relations[1].on_update = Some(sql::ReferentialAction::Cascade);
+        let model = Model::new(smodel.clone()).expect("referential actions should be valid");
+
+        use crate::sql::GenerateSql;
+        let sql = model.generate_sql();
+        assert!(sql[4].ends_with(
+            "category_id INTEGER, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL ON UPDATE CASCADE);"
+        ));
+        assert_eq!(
+            sql[6],
+            "CREATE TABLE part_categories(part_id INTEGER NOT NULL, category_id INTEGER NOT NULL, PRIMARY KEY (part_id, category_id), FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE RESTRICT, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE RESTRICT);"
+        );
+
+        let mut invalid = smodel.clone();
+        invalid.relations[1].required = true;
+        Model::new(invalid).expect_err("SetNull on a required relation should be invalid");
+
+        let mut invalid = smodel;
+        invalid.relations[0].on_update = Some(sql::ReferentialAction::SetNull);
+        Model::new(invalid).expect_err("SetNull on a ManyToMany relation should be invalid");
+    }
+
+    #[test]
+    fn model_self_referential() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(category_entity());
+        let mut subcategory = hasprice_relation();
+        subcategory.urn = "sampleperry:subcategory".into();
+        subcategory.destination = "sampleperry:category".into();
+        subcategory.required = false;
+        smodel.relations.push(subcategory);
+        let mut related = categorised_relation();
+        related.urn = "sampleperry:relatedto".into();
+        related.origin = "sampleperry:category".into();
+        smodel.relations.push(related);
+        let model = Model::new(smodel.clone()).expect("self-referential relations should be valid");
+
+        use crate::sql::GenerateSql;
+        let sql = model.generate_sql();
+        assert!(sql[0].ends_with(
+            "name TEXT UNIQUE NOT NULL, parent_category_id INTEGER, FOREIGN KEY (parent_category_id) REFERENCES categories (id) ON DELETE CASCADE);"
+        ));
+        assert_eq!(
+            sql[2],
+            "CREATE TABLE category_categories(parent_category_id INTEGER NOT NULL, child_category_id INTEGER NOT NULL, PRIMARY KEY (parent_category_id, child_category_id), FOREIGN KEY (parent_category_id) REFERENCES categories (id) ON DELETE CASCADE, FOREIGN KEY (child_category_id) REFERENCES categories (id) ON DELETE CASCADE);"
+        );
+
+        let schema = model.relation_json_schema("sampleperry:relatedto").unwrap();
+        let schema = serde_json::to_value(schema).unwrap();
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["parent_category_id", "child_category_id"])
+        );
+        let schema = model.entity_json_schema("sampleperry:category").unwrap();
+        let schema = serde_json::to_value(schema).unwrap();
+        assert_eq!(
+            schema["properties"]["parent_category_id"],
+            serde_json::json!({ "type": ["integer", "null"] })
+        );
+
+        let mut invalid = smodel;
+        invalid.relations[0].required = true;
+        Model::new(invalid).expect_err("required self-referential relation should be invalid");
+    }
+
+    #[test]
+    fn model_relations_between_same_entities() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(category_entity());
+        smodel.entities.push(price_entity());
+        smodel.relations.push(pricedby_relation());
+        let mut costprice = pricedby_relation();
+        costprice.id = 13;
+        costprice.urn = "sampleperry:costprice".into();
+        costprice.required = false;
+        smodel.relations.push(costprice);
+        smodel.relations.push(categorised_relation());
+        let mut maincategory = categorised_relation();
+        maincategory.id = 14;
+        maincategory.urn = "sampleperry:maincategory".into();
+        smodel.relations.push(maincategory);
+        smodel.relations.push(hasprice_relation());
+        let model = Model::new(smodel.clone()).expect("relations should be qualified");
+
+        use crate::sql::GenerateSql;
+        let sql = model.generate_sql();
+        assert!(sql[4].contains(
+            "part_id INTEGER NOT NULL, costprice_part_id INTEGER, category_id INTEGER NOT NULL, "
+        ));
+        assert!(sql[6].starts_with("CREATE TABLE part_categories(part_id INTEGER NOT NULL, "));
+        assert!(sql[7].starts_with("CREATE TABLE maincategory(part_id INTEGER NOT NULL, "));
+        assert_eq!(
+            model.table_names(),
+            vec![
+                "parts",
+                "categories",
+                "prices",
+                "part_categories",
+                "maincategory"
+            ]
+        );
+        assert!(model.is_relation_table("maincategory"));
+        assert!(!model.is_relation_table("categorisedby"));
+
+        // Adding relations leaves the names of existing ones unchanged
+        let mut first = smodel.clone();
+        first.relations.retain(|rel| rel.id != 13 && rel.id != 14);
+        let before = Model::new(first).unwrap().relation_mappings();
+        let after = model.relation_mappings();
+        assert_eq!(before[0], after[0]);
+        assert_eq!(before[1], after[2]);
+
+        let mappings = model.relation_mappings();
+        assert_eq!(
+            mappings[1],
+            RelationMapping {
+                urn: "sampleperry:costprice".into(),
+                table: "prices".into(),
+                origin_key: "costprice_part_id".into(),
+                destination_key: "id".into(),
+            }
+        );
+        assert_eq!(mappings[3].table, "maincategory");
+        assert_eq!(mappings[4].origin_key, "category_id");
+
+        let schema = model.entity_json_schema("sampleperry:price").unwrap();
+        let schema = serde_json::to_value(schema).unwrap();
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["value", "part_id", "category_id"])
+        );
+
+        let mut invalid = smodel.clone();
+        invalid.relations[3].urn = "sampleperry:prices".into();
+        Model::new(invalid).expect_err("relation table colliding with entity table");
+
+        let mut invalid = smodel;
+        invalid.entities[2].attributes[0].id = "costprice_part_id".into();
+        Model::new(invalid).expect_err("foreign key colliding with attribute");
+    }
+
+    fn country_entity() -> Entity {
+        serde_json::from_str(
+            r#"{
+            "id": 11,
+            "urn": "global:country",
+            "singular": "country",
+            "plural": "countries",
+            "name": "Country",
+            "description": "A country",
+            "visibility": "Global",
+            "identity": "Uuid",
+            "attributes": [
+                {
+                    "id": "code",
+                    "name": "Code",
+                    "required": true,
+                    "unique": true,
+                    "type": "string"
+                }
+            ]
+        }"#,
+        )
+        .unwrap()
+    }
+
+    fn madein_relation() -> Relation {
+        serde_json::from_str(
+            r#"{
+            "id": 12,
+            "urn": "sampleperry:madein",
+            "name": "Made In",
+            "description": "Country of origin of a part",
+            "visibility": "Tenant",
+            "origin": "global:country",
+            "destination": "sampleperry:part",
+            "cardinality": "OneToMany",
+            "required": false,
+            "attributes": []
+        }"#,
+        )
+        .unwrap()
+    }
+
+    fn global_model(country: Entity) -> Model {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.tenant = "global".into();
+        smodel.entities.push(country);
+        Model::new(smodel).unwrap()
+    }
+
+    #[test]
+    fn model_imports() {
+        let owners = [global_model(country_entity())];
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.imports.push("global:country".into());
+        smodel.relations.push(madein_relation());
+        let model =
+            Model::importing(smodel.clone(), &owners).expect("model with imports should be valid");
+
+        use crate::sql::GenerateSql;
+        let sql = model.generate_sql();
+        assert_eq!(sql.len(), 2);
+        assert!(sql[0].ends_with(
+            "country_id TEXT, FOREIGN KEY (country_id) REFERENCES countries (id) ON DELETE CASCADE);"
+        ));
+        assert_eq!(model.table_names(), vec!["parts"]);
+
+        let mut country = country_entity();
+        country.visibility = Visibility::Tenant;
+        let err = Model::importing(smodel.clone(), &[global_model(country)])
+            .expect_err("imports must be Global");
+        assert_eq!(err.pointer(), "/imports/0");
+
+        let err = Model::new(smodel.clone()).expect_err("imports must be found");
+        assert_eq!(err.code(), ErrorCode::NotFound);
+        assert_eq!(err.pointer(), "/imports/0");
+
+        let mut invalid = smodel.clone();
+        invalid.imports.clear();
+        Model::importing(invalid, &owners).expect_err("foreign entities must be imported");
+
+        let mut invalid = smodel.clone();
+        invalid.relations[0].origin = "sampleperry:part".into();
+        invalid.relations[0].destination = "global:country".into();
+        Model::importing(invalid, &owners).expect_err("imported tables cannot hold foreign keys");
+
+        let mut invalid = smodel;
+        invalid.entities[0].plural = "countries".into();
+        Model::importing(invalid, &owners).expect_err("tables cannot collide with imported ones");
+    }
+
+    #[test]
+    fn model_join_keys() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(category_entity());
+        let mut categorised = categorised_relation();
+        categorised.attributes = serde_json::from_str(
+            r#"[{ "id": "main", "name": "Main", "type": "bool", "required": true }]"#,
+        )
+        .unwrap();
+        categorised.join_key = JoinKey::Surrogate;
+        categorised.unique_constraints.push(UniqueConstraint {
+            attributes: vec!["part_id".into(), "main".into()],
+            relations: vec![],
+        });
+        smodel.relations.push(categorised);
+        let model = Model::new(smodel.clone()).expect("join keys should be valid");
+
+        use crate::sql::GenerateSql;
+        assert_eq!(
+            model.generate_sql()[4],
+            "CREATE TABLE part_categories(id INTEGER PRIMARY KEY AUTOINCREMENT, part_id INTEGER NOT NULL, category_id INTEGER NOT NULL, main BOOLEAN NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE, UNIQUE (part_id, category_id), UNIQUE (part_id, main));"
+        );
+        assert_eq!(
+            model.table_unique_attributes("part_categories"),
+            Some(vec!["part_id".into(), "category_id".into(), "main".into()])
+        );
+        assert_eq!(model.table_unique_attributes("categories_parts"), None);
+
+        let mut uuid = smodel.clone();
+        uuid.identity = Identity::Uuid;
+        let model = Model::new(uuid).expect("join keys with uuid ids should be valid");
+        assert!(model.generate_sql()[4]
+            .starts_with("CREATE TABLE part_categories(id TEXT PRIMARY KEY DEFAULT (lower(hex("));
+
+        let mut invalid = smodel.clone();
+        invalid.relations[0].unique_constraints[0].attributes[1] = "primary".into();
+        Model::new(invalid).expect_err("unknown attribute in relation unique constraint");
+
+        let mut invalid = smodel;
+        invalid.relations[0].unique_constraints[0].relations = vec!["sampleperry:part".into()];
+        Model::new(invalid).expect_err("relation unique constraints cannot use relations");
+    }
+
+    #[test]
+    fn model_ordered_relation() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(category_entity());
+        smodel.relations.push(categorised_relation());
+        smodel.relations[0].ordered = true;
+        let model = Model::new(smodel.clone()).expect("ordered relation should be valid");
+
+        use crate::sql::GenerateSql;
+        assert_eq!(
+            model.generate_sql()[4],
+            "CREATE TABLE part_categories(part_id INTEGER NOT NULL, category_id INTEGER NOT NULL, position INTEGER NOT NULL, PRIMARY KEY (part_id, category_id), FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE, UNIQUE (part_id, position));"
+        );
+        assert_eq!(
+            model.table_unique_attributes("part_categories"),
+            Some(vec![
+                "part_id".into(),
+                "category_id".into(),
+                "position".into()
+            ])
+        );
+
+        let schema = model
+            .relation_json_schema("sampleperry:categorisedby")
+            .unwrap();
+        let schema = serde_json::to_value(schema).unwrap();
+        assert_eq!(
+            schema["properties"]["position"],
+            serde_json::json!({ "type": "integer", "minimum": 0.0 })
+        );
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["part_id", "category_id", "position"])
+        );
+
+        let statements = model.reorder_sql("sampleperry:categorisedby").unwrap();
+        assert_eq!(
+            statements.move_to[3],
+            "UPDATE part_categories SET position = ?3 WHERE part_id = ?1 AND category_id = ?2;"
+        );
+
+        let mut unordered = smodel.clone();
+        unordered.relations[0].ordered = false;
+        let unordered = Model::new(unordered).unwrap();
+        assert!(unordered.reorder_sql("sampleperry:categorisedby").is_err());
+
+        let mut invalid = smodel;
+        invalid.entities.push(price_entity());
+        invalid.relations.push(pricedby_relation());
+        invalid.relations[1].ordered = true;
+        Model::new(invalid).expect_err("only ManyToMany relations can be ordered");
+    }
+
+    #[test]
+    fn model_inverse_names() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(category_entity());
+        smodel.entities.push(price_entity());
+        smodel.relations.push(categorised_relation());
+        smodel.relations.push(pricedby_relation());
+        smodel.relations[0].inverse_name = Some("Categorises".into());
+        smodel.relations[0].inverse_description = Some("Parts of a category".into());
+        let model = Model::new(smodel.clone()).expect("inverse names should be valid");
+
+        assert_eq!(
+            model.navigation("sampleperry:part").unwrap(),
+            vec![
+                Navigation {
+                    relation: "sampleperry:categorisedby".into(),
+                    direction: Direction::Forward,
+                    name: "Categorised By".into(),
+                    description: "Categorization relation".into(),
+                    target: "sampleperry:category".into(),
+                    cardinality: Cardinality::ManyToMany,
+                },
+                Navigation {
+                    relation: "sampleperry:pricedby".into(),
+                    direction: Direction::Forward,
+                    name: "Priced By".into(),
+                    description: "Priced relation".into(),
+                    target: "sampleperry:price".into(),
+                    cardinality: Cardinality::OneToMany,
+                },
+            ]
+        );
+        let navigation = model.navigation("sampleperry:category").unwrap();
+        assert_eq!(navigation[0].name, "Categorises");
+        assert_eq!(navigation[0].description, "Parts of a category");
+        assert_eq!(navigation[0].target, "sampleperry:part");
+        assert!(model.navigation("sampleperry:foo").is_err());
+
+        let mut invalid = smodel.clone();
+        invalid.relations[0].inverse_name = Some("".into());
+        Model::new(invalid).expect_err("empty inverse name should be invalid");
+
+        let mut invalid = smodel.clone();
+        invalid.relations.push(hasprice_relation());
+        invalid.relations[2].name = "Categorises".into();
+        Model::new(invalid).expect_err("names read from category should be unique");
+
+        let mut invalid = smodel.clone();
+        invalid.relations[0].origin = "sampleperry:category".into();
+        invalid.relations[0].inverse_name = Some("Categorised By".into());
+        Model::new(invalid).expect_err("inverse of self-referential relation needs another name");
+
+        let mut valid = smodel.clone();
+        valid.relations[0].origin = "sampleperry:category".into();
+        valid.relations[0].inverse_name = None;
+        valid.relations[0].inverse_description = None;
+        Model::new(valid).expect("only explicit inverse names should be checked");
+
+        let mut valid = smodel;
+        valid.relations.push(pricedby_relation());
+        valid.relations[2].urn = "sampleperry:costprice".into();
+        valid.relations[2].id = 13;
+        valid.relations[2].required = false;
+        Model::new(valid).expect("forward names may repeat");
+    }
+
+    fn price_subtype_entity() -> Entity {
+        let mut entity = price_seller_entity();
+        entity.extends = Some("sampleperry:price".to_owned());
+        entity.attributes.remove(0);
+        entity.unique_constraints[0].relations.clear();
+        entity
+    }
+
+    #[test]
+    fn model_inheritance() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(price_entity());
+        smodel.entities.push(price_subtype_entity());
+        smodel.relations.push(pricedby_relation());
+        let model = Model::new(smodel.clone()).expect("single table hierarchy should be valid");
+
+        use crate::sql::GenerateSql;
+        let sql = model.generate_sql();
+        assert_eq!(sql.len(), 4);
+        assert!(sql[2].contains(
+            "value REAL NOT NULL, entity_type TEXT DEFAULT 'price' NOT NULL CHECK (entity_type IN ('price', 'price_seller')), seller TEXT CHECK (entity_type NOT IN ('price_seller') OR seller IS NOT NULL), part_id INTEGER NOT NULL"
+        ));
+        assert!(sql[2].ends_with("UNIQUE (seller));"));
+        assert_eq!(model.table_names(), vec!["parts", "prices"]);
+        assert_eq!(
+            model.table_unique_attributes("prices"),
+            Some(vec!["seller".into()])
+        );
+        assert_eq!(model.table_unique_attributes("prices_seller"), None);
+
+        let schema = model
+            .entity_json_schema("sampleperry:price_seller")
+            .unwrap();
+        let schema = serde_json::to_value(schema).unwrap();
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["seller", "entity_type"])
+        );
+        assert_eq!(
+            schema["properties"]["entity_type"]["enum"],
+            serde_json::json!(["price_seller"])
+        );
+        assert_eq!(schema["allOf"][0]["$id"], "sampleperry:price");
+        assert_eq!(
+            schema["allOf"][0]["required"],
+            serde_json::json!(["value", "part_id"])
+        );
+
+        smodel.entities[1].inheritance = Some(Inheritance::Joined);
+        let model = Model::new(smodel.clone()).expect("joined hierarchy should be valid");
+        let sql = model.generate_sql();
+        assert_eq!(sql.len(), 5);
+        assert!(sql[2].contains("value REAL NOT NULL, part_id INTEGER NOT NULL"));
+        assert_eq!(
+            sql[4],
+            "CREATE TABLE prices_seller(id INTEGER PRIMARY KEY NOT NULL, seller TEXT NOT NULL, FOREIGN KEY (id) REFERENCES prices (id) ON DELETE CASCADE, UNIQUE (seller));"
+        );
+        let schema = model
+            .entity_json_schema("sampleperry:price_seller")
+            .unwrap();
+        let schema = serde_json::to_value(schema).unwrap();
+        assert!(schema["properties"].get("entity_type").is_none());
+
+        // Unique constraints of joined subtypes cannot span tables
+        let mut invalid = smodel.clone();
+        invalid.entities[2].unique_constraints[0]
+            .attributes
+            .push("value".to_owned());
+        Model::new(invalid).expect_err("unique constraint across tables should be invalid");
+
+        let mut invalid = smodel.clone();
+        invalid.entities[1].inheritance = None;
+        invalid.entities[1].attributes[0].list = true;
+        invalid.entities[2].unique_constraints[0]
+            .attributes
+            .push("value".to_owned());
+        let err = Model::new(invalid).expect_err("inherited list in a unique constraint");
+        assert_eq!(
+            err.pointer(),
+            "/entities/2/unique_constraints/0/attributes/1"
+        );
+
+        let mut invalid = smodel.clone();
+        invalid.entities[2].inheritance = Some(Inheritance::Joined);
+        Model::new(invalid).expect_err("inheritance of a subtype should be invalid");
+
+        let mut invalid = smodel.clone();
+        invalid.entities[2].attributes[0].id = "value".to_owned();
+        invalid.entities[2].unique_constraints.clear();
+        Model::new(invalid).expect_err("redefined attribute should be invalid");
+
+        let mut invalid = smodel.clone();
+        invalid.entities[2].extends = Some("sampleperry:foo".to_owned());
+        Model::new(invalid).expect_err("missing parent should be invalid");
+
+        let mut invalid = smodel.clone();
+        invalid.entities[1].extends = Some("sampleperry:price_seller".to_owned());
+        Model::new(invalid).expect_err("inheritance cycle should be invalid");
+
+        let mut invalid = smodel;
+        invalid.entities[2].identity = Some(Identity::Uuid);
+        Model::new(invalid).expect_err("subtype with its own identity should be invalid");
+    }
+
+    #[test]
+    fn model_error_details() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(price_entity());
+        smodel.relations.push(pricedby_relation());
+
+        let mut invalid = smodel.clone();
+        invalid.entities[1].attributes[0].default = Some(serde_json::json!("cheap"));
+        let err = Model::new(invalid).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidAttribute);
+        assert_eq!(err.urn(), Some("sampleperry:price"));
+        assert_eq!(err.attribute(), Some("value"));
+        assert_eq!(err.pointer(), "/entities/1/attributes/0/default");
+
+        let mut invalid = smodel.clone();
+        invalid.relations[0].ordered = true;
+        let err = Model::new(invalid).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidRelation);
+        assert_eq!(err.urn(), Some("sampleperry:pricedby"));
+        assert_eq!(err.attribute(), None);
+        assert_eq!(err.pointer(), "/relations/0/ordered");
+
+        let mut invalid = smodel.clone();
+        invalid.relations[0].origin = "sampleperry:foo".to_owned();
+        let err = Model::new(invalid).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::NotFound);
+        assert_eq!(err.pointer(), "/relations/0/origin");
+
+        let mut invalid = smodel;
+        invalid.entities[1].attributes[0].id = "part_id".to_owned();
+        let err = Model::new(invalid).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Conflict);
+        assert_eq!(err.urn(), Some("sampleperry:pricedby"));
+        assert_eq!(err.pointer(), "/relations/0");
+
+        let err = Error::from(serde_json::from_str::<SerdeModel>("{}").err().unwrap());
+        assert_eq!(err.code(), ErrorCode::Serialization);
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["code"], "Serialization");
+        assert_eq!(value["pointer"], "");
+        assert!(value.get("urn").is_none());
+    }
+
+    #[test]
+    fn model_diagnose() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(price_entity());
+        smodel.relations.push(pricedby_relation());
+        assert!(Model::diagnose(smodel.clone()).is_empty());
+
+        let mut invalid = smodel.clone();
+        invalid.entities[0].unique_constraints = vec![
+            UniqueConstraint {
+                attributes: vec!["label".to_owned()],
+                relations: vec![],
+            },
+            UniqueConstraint {
+                attributes: vec!["stock".to_owned(), "label".to_owned()],
+                relations: vec![],
+            },
+            UniqueConstraint {
+                attributes: vec!["label".to_owned(), "stock".to_owned()],
+                relations: vec![],
+            },
+        ];
+        invalid.entities[1].plural = "price".to_owned();
+        invalid.entities[1].attributes[0].default = Some(serde_json::json!("cheap"));
+        invalid.relations[0].ordered = true;
+        invalid.relations[0].inverse_name = Some(" ".to_owned());
+        let diagnostics = Model::diagnose(invalid);
+        let summary: Vec<(Severity, ErrorCode, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.error.code(), d.error.pointer()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    Severity::Error,
+                    ErrorCode::InvalidEntity,
+                    "/entities/1/plural"
+                ),
+                (
+                    Severity::Error,
+                    ErrorCode::InvalidAttribute,
+                    "/entities/1/attributes/0/default"
+                ),
+                (
+                    Severity::Error,
+                    ErrorCode::InvalidRelation,
+                    "/relations/0/inverse_name"
+                ),
+                (
+                    Severity::Error,
+                    ErrorCode::InvalidRelation,
+                    "/relations/0/ordered"
+                ),
+                (
+                    Severity::Warning,
+                    ErrorCode::Redundant,
+                    "/entities/0/unique_constraints/0"
+                ),
+                (
+                    Severity::Warning,
+                    ErrorCode::Redundant,
+                    "/entities/0/unique_constraints/2"
+                ),
+            ]
+        );
+
+        // Checks needing both entities of relations are skipped without them,
+        // the others still run
+        let mut invalid = smodel;
+        invalid.relations[0].origin = "sampleperry:foo".to_owned();
+        invalid.relations[0].destination = "sampleperry:bar".to_owned();
+        invalid.entities[0].unique_constraints = vec![UniqueConstraint {
+            attributes: vec!["label".to_owned()],
+            relations: vec!["sampleperry:unknown".to_owned()],
+        }];
+        let diagnostics = Model::diagnose(invalid);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[1].error.pointer(), "/relations/0/destination");
+        assert_eq!(
+            diagnostics[2].error.pointer(),
+            "/entities/0/unique_constraints/0/relations/0"
+        );
+        let value = serde_json::to_value(&diagnostics[0]).unwrap();
+        assert_eq!(value["severity"], "Error");
+        assert_eq!(value["code"], "NotFound");
+        assert_eq!(value["urn"], "sampleperry:pricedby");
+    }
+
+    #[test]
+    fn model_duplicates() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(category_entity());
+        smodel.entities.push(price_entity());
+        smodel.relations.push(pricedby_relation());
+
+        let mut invalid = smodel.clone();
+        invalid.entities.push(part_entity());
+        invalid.entities[1].plural = "part".to_owned();
+        invalid.entities[2].id = 2;
+        let mut relation = pricedby_relation();
+        relation.urn = "sampleperry:category".to_owned();
+        invalid.relations.push(relation);
+        invalid.imports.push("global:country".to_owned());
+        invalid.imports.push("global:country".to_owned());
+        let owners = [global_model(country_entity())];
+        assert!(Model::importing(invalid.clone(), &owners).is_err());
+
+        let diagnostics = Model::diagnose_importing(invalid, &owners);
+        let summary: Vec<(&str, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.error.urn().unwrap(), d.error.pointer()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("sampleperry:category", "/entities/1/plural"),
+                ("sampleperry:price", "/entities/2/id"),
+                ("sampleperry:part", "/entities/3/urn"),
+                ("sampleperry:part", "/entities/3/singular"),
+                ("sampleperry:part", "/entities/3/plural"),
+                ("sampleperry:part", "/entities/3/id"),
+                ("global:country", "/imports/1"),
+                ("global:country", "/imports/1"),
+                ("global:country", "/imports/1"),
+                ("global:country", "/imports/1"),
+                ("sampleperry:category", "/relations/1/urn"),
+                ("sampleperry:category", "/relations/1/id"),
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.error.code() == ErrorCode::Conflict));
+    }
+
+    #[test]
+    fn model_physical_names() {
+        let mut smodel = empty_serde_model().unwrap();
+        smodel.entities.push(part_entity());
+        smodel.entities.push(price_entity());
+        smodel.relations.push(pricedby_relation());
+
+        let mut reserved = smodel.clone();
+        reserved.entities[0].attributes[1].id = "order".to_owned();
+        let err = Model::new(reserved).expect_err("reserved column should fail");
+        assert_eq!(err.code(), ErrorCode::Reserved);
+        assert_eq!(err.urn(), Some("sampleperry:part"));
+        assert_eq!(err.pointer(), "/entities/0/attributes/1/id");
+        assert_eq!(err.suggestion(), Some("part_order"));
+
+        let mut keyword = smodel;
+        keyword.entities[0].attributes[1].id = "key".to_owned();
+        assert!(Model::new(keyword.clone()).is_ok());
+        let diagnostics = Model::diagnose(keyword);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].error.code(), ErrorCode::Reserved);
+        assert_eq!(diagnostics[0].error.suggestion(), Some("part_key"));
+    }
+
+    #[test]
+    fn model_uuid_identity() {
+        let mut smodel = uuid_serde_model();
+        smodel.entities[1].identity = Some(Identity::Ulid);
+        let model = Model::new(smodel).expect("model with uuid ids should be valid");
+
+        use crate::sql::GenerateSql;
+        let sql = model.generate_sql();
+        assert!(sql[0].starts_with(
+            "CREATE TABLE parts(id TEXT PRIMARY KEY DEFAULT (lower(hex(randomblob(4)) || '-' ||"
+        ));
+        assert!(sql[2].starts_with("CREATE TABLE categories(id TEXT PRIMARY KEY NOT NULL, "));
+        assert!(sql[4].contains(
+            "part_id TEXT NOT NULL, FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE"
+        ));
+        assert_eq!(
+            sql[6],
+            "CREATE TABLE part_categories(part_id TEXT NOT NULL, category_id TEXT NOT NULL, PRIMARY KEY (part_id, category_id), FOREIGN KEY (part_id) REFERENCES parts (id) ON DELETE CASCADE, FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE);"
+        );
+
+        let schema = model
+            .relation_json_schema("sampleperry:categorisedby")
+            .unwrap();
+        let schema = serde_json::to_value(schema).unwrap();
+        assert_eq!(schema["properties"]["part_id"]["type"], "string");
+        assert_eq!(
+            schema["properties"]["category_id"]["pattern"],
+            "^[0-9A-HJKMNP-TV-Z]{26}$"
+        );
+        assert!(model.relation_json_schema("sampleperry:foo").is_err());
     }
 
     #[test]
@@ -0,0 +1,391 @@
+use crate::error::{Diagnostic, Error, ErrorCode, Severity};
+use inflector::Inflector;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Keywords SQLite rejects as unquoted table or column names.
+const SQLITE_RESERVED: &[&str] = &[
+    "add",
+    "all",
+    "alter",
+    "and",
+    "as",
+    "autoincrement",
+    "between",
+    "case",
+    "check",
+    "collate",
+    "commit",
+    "constraint",
+    "create",
+    "current_date",
+    "current_time",
+    "current_timestamp",
+    "default",
+    "deferrable",
+    "delete",
+    "distinct",
+    "drop",
+    "else",
+    "escape",
+    "except",
+    "exists",
+    "foreign",
+    "from",
+    "group",
+    "having",
+    "if",
+    "in",
+    "index",
+    "insert",
+    "intersect",
+    "into",
+    "is",
+    "isnull",
+    "join",
+    "limit",
+    "not",
+    "nothing",
+    "notnull",
+    "null",
+    "on",
+    "or",
+    "order",
+    "primary",
+    "references",
+    "returning",
+    "select",
+    "set",
+    "table",
+    "then",
+    "to",
+    "transaction",
+    "union",
+    "unique",
+    "update",
+    "using",
+    "values",
+    "when",
+    "where",
+];
+
+/// Keywords SQLite accepts as names, though they read ambiguously in queries.
+const SQLITE_KEYWORDS: &[&str] = &[
+    "abort",
+    "action",
+    "after",
+    "always",
+    "analyze",
+    "asc",
+    "attach",
+    "before",
+    "begin",
+    "by",
+    "cascade",
+    "cast",
+    "column",
+    "conflict",
+    "cross",
+    "current",
+    "database",
+    "deferred",
+    "desc",
+    "detach",
+    "do",
+    "each",
+    "end",
+    "exclude",
+    "exclusive",
+    "explain",
+    "fail",
+    "filter",
+    "first",
+    "following",
+    "for",
+    "full",
+    "generated",
+    "glob",
+    "groups",
+    "ignore",
+    "immediate",
+    "indexed",
+    "initially",
+    "inner",
+    "instead",
+    "key",
+    "last",
+    "left",
+    "like",
+    "match",
+    "materialized",
+    "natural",
+    "no",
+    "nulls",
+    "of",
+    "offset",
+    "others",
+    "outer",
+    "over",
+    "partition",
+    "plan",
+    "pragma",
+    "preceding",
+    "query",
+    "raise",
+    "range",
+    "recursive",
+    "regexp",
+    "reindex",
+    "release",
+    "rename",
+    "replace",
+    "restrict",
+    "right",
+    "rollback",
+    "row",
+    "rows",
+    "savepoint",
+    "temp",
+    "temporary",
+    "ties",
+    "trigger",
+    "unbounded",
+    "vacuum",
+    "view",
+    "virtual",
+    "window",
+    "with",
+    "without",
+];
+
+/// Prefix of the tables SQLite keeps for itself.
+const SQLITE_INTERNAL: &str = "sqlite_";
+
+/// Built-in scalars and root operation types of GraphQL schemas.
+const GRAPHQL_RESERVED: &[&str] = &[
+    "Boolean",
+    "Float",
+    "ID",
+    "Int",
+    "String",
+    "Query",
+    "Mutation",
+    "Subscription",
+];
+
+/// Prefix GraphQL keeps for introspection.
+const GRAPHQL_INTERNAL: &str = "__";
+
+/// Where the generated schema is used: SQLite runs the generated SQL and
+/// GraphQL types and fields are named after its tables and columns.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Target {
+    Sqlite,
+    GraphQL,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Table,
+    Column,
+    Trigger,
+}
+
+impl Kind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Table => "table",
+            Kind::Column => "column",
+            Kind::Trigger => "trigger",
+        }
+    }
+}
+
+/// A name of the generated schema, along with the value of the spec it
+/// derives from.
+pub struct Identifier {
+    pub kind: Kind,
+    pub name: String,
+    /// Table of a column.
+    pub table: Option<String>,
+    pub urn: String,
+    pub pointer: String,
+    /// Prefix giving a safe name, e.g. the singular of an entity for its columns.
+    pub prefix: String,
+}
+
+impl Identifier {
+    fn describe(&self) -> String {
+        match &self.table {
+            Some(table) => format!(
+                "{} '{}' of table '{}'",
+                self.kind.as_str(),
+                self.name,
+                table
+            ),
+            None => format!("{} '{}'", self.kind.as_str(), self.name),
+        }
+    }
+}
+
+/// Severity of using a name in a target, if reserved there.
+pub fn reserved(target: Target, kind: Kind, name: &str) -> Option<Severity> {
+    match target {
+        Target::Sqlite => {
+            let lower = name.to_lowercase();
+            let internal = kind == Kind::Table && lower.starts_with(SQLITE_INTERNAL);
+            if SQLITE_RESERVED.contains(&lower.as_str()) || internal {
+                Some(Severity::Error)
+            } else if SQLITE_KEYWORDS.contains(&lower.as_str()) {
+                Some(Severity::Warning)
+            } else {
+                None
+            }
+        }
+        Target::GraphQL => {
+            let formatted = graphql_name(kind, name)?;
+            let builtin = kind == Kind::Table && GRAPHQL_RESERVED.contains(&formatted.as_str());
+            match builtin || formatted.starts_with(GRAPHQL_INTERNAL) {
+                true => Some(Severity::Warning),
+                false => None,
+            }
+        }
+    }
+}
+
+/// Name of the GraphQL type of a table or field of a column.
+fn graphql_name(kind: Kind, name: &str) -> Option<String> {
+    match kind {
+        Kind::Table => Some(name.to_pascal_case()),
+        Kind::Column => Some(name.to_snake_case()),
+        Kind::Trigger => None,
+    }
+}
+
+/// Prefix a name until it is neither reserved in SQLite nor taken.
+pub fn safe_name(kind: Kind, name: &str, prefix: &str, taken: &dyn Fn(&str) -> bool) -> String {
+    let mut safe = name.to_owned();
+    while reserved(Target::Sqlite, kind, &safe).is_some() || taken(&safe) {
+        safe = format!("{}_{}", prefix, safe);
+    }
+    safe
+}
+
+/// Check that identifiers are distinct within their scope, as told apart
+/// by each target, and not reserved there.
+pub fn check(identifiers: &[Identifier], targets: &[Target]) -> Vec<Diagnostic> {
+    // Identifiers of a kind, within a table for columns, share a scope
+    let scope = |id: &Identifier| (id.kind.as_str(), id.table.clone().unwrap_or_default());
+    let taken = |id: &Identifier, name: &str| {
+        identifiers
+            .iter()
+            .any(|other| scope(other) == scope(id) && other.name.eq_ignore_ascii_case(name))
+    };
+    let diagnostic = |severity: Severity, code: ErrorCode, id: &Identifier, msg: String| {
+        let suggestion = safe_name(id.kind, &id.name, &id.prefix, &|name| taken(id, name));
+        let error = Error::with_code(code, &msg)
+            .with_urn(&id.urn)
+            .with_suggestion(&suggestion)
+            .at(&id.pointer);
+        Diagnostic {
+            severity,
+            error,
+            rule: None,
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    for target in targets {
+        // SQLite compares names regardless of case
+        let mut seen: BTreeMap<(&str, String, String), &Identifier> = BTreeMap::new();
+        for id in identifiers {
+            let name = match target {
+                Target::Sqlite => Some(id.name.to_lowercase()),
+                Target::GraphQL => graphql_name(id.kind, &id.name),
+            };
+            let name = match name {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let (kind, table) = scope(id);
+            if let Some(first) = seen.insert((kind, table, name.clone()), id) {
+                let severity = match target {
+                    Target::Sqlite => Severity::Error,
+                    Target::GraphQL => Severity::Warning,
+                };
+                let msg = format!(
+                    "{} is named '{}' in {:?} like {}",
+                    id.describe(),
+                    name,
+                    target,
+                    first.describe()
+                );
+                diagnostics.push(diagnostic(severity, ErrorCode::Conflict, id, msg));
+            }
+
+            if let Some(severity) = reserved(*target, id.kind, &id.name) {
+                let msg = format!("{} is reserved in {:?}", id.describe(), target);
+                diagnostics.push(diagnostic(severity, ErrorCode::Reserved, id, msg));
+            }
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str) -> Identifier {
+        Identifier {
+            kind: Kind::Column,
+            name: name.to_owned(),
+            table: Some("parts".to_owned()),
+            urn: "sampleperry:part".to_owned(),
+            pointer: "/entities/0".to_owned(),
+            prefix: "part".to_owned(),
+        }
+    }
+
+    #[test]
+    fn physical_reserved() {
+        assert_eq!(
+            reserved(Target::Sqlite, Kind::Column, "Order"),
+            Some(Severity::Error)
+        );
+        assert_eq!(
+            reserved(Target::Sqlite, Kind::Column, "key"),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            reserved(Target::Sqlite, Kind::Table, "sqlite_parts"),
+            Some(Severity::Error)
+        );
+        assert_eq!(reserved(Target::Sqlite, Kind::Column, "label"), None);
+        assert_eq!(reserved(Target::GraphQL, Kind::Table, "queries"), None);
+        assert_eq!(
+            reserved(Target::GraphQL, Kind::Table, "query"),
+            Some(Severity::Warning)
+        );
+        assert_eq!(reserved(Target::GraphQL, Kind::Column, "query"), None);
+    }
+
+    #[test]
+    fn physical_check() {
+        let identifiers = vec![column("label"), column("order"), column("Label")];
+        let diagnostics = check(&identifiers, &[Target::Sqlite]);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].error.code(), ErrorCode::Reserved);
+        assert_eq!(diagnostics[0].error.suggestion(), Some("part_order"));
+        assert_eq!(diagnostics[1].error.code(), ErrorCode::Conflict);
+        assert_eq!(diagnostics[1].error.suggestion(), Some("part_Label"));
+
+        let identifiers = vec![column("unit_price"), column("unitPrice")];
+        assert!(check(&identifiers, &[Target::Sqlite]).is_empty());
+        let diagnostics = check(&identifiers, &[Target::GraphQL]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+}
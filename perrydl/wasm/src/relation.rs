@@ -1,6 +1,7 @@
-use crate::common::{Attribute, Visibility};
-use crate::error::{Error, Result};
-use crate::json_schema::{self, JsonSchema, Type};
+use crate::common::{Attribute, Identity, TimestampFormat, UniqueConstraint, Visibility};
+use crate::error::{Error, ErrorCode, Result};
+use crate::json_schema::{self, JsonSchema};
+use crate::sql::ReferentialAction;
 use crate::urn;
 use serde::{Deserialize, Serialize};
 
@@ -8,6 +9,29 @@ use serde::{Deserialize, Serialize};
 pub enum Cardinality {
     OneToMany,
     ManyToMany,
+    /// Like OneToMany, with each origin related to at most one destination.
+    OneToOne,
+}
+
+/// Column holding the position of links of ordered relations.
+pub const POSITION: &str = "position";
+
+/// Primary key of the tables of ManyToMany relations.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum JoinKey {
+    /// The pair of related ids, relating two entities at most once.
+    #[default]
+    Composite,
+    /// An `id` column of its own, with the pair of related ids unique.
+    Surrogate,
+}
+
+impl Cardinality {
+    /// Whether the relation is stored as a foreign key of the destination
+    /// table rather than in a table of its own.
+    pub fn is_foreign_key(&self) -> bool {
+        matches!(self, Cardinality::OneToMany | Cardinality::OneToOne)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -16,72 +40,291 @@ pub struct Relation {
     pub urn: String,
     pub name: String,
     pub description: String,
+    /// Name of the relation read from the destination, e.g. "Categorises"
+    /// for "Categorised By".
+    #[serde(default)]
+    pub inverse_name: Option<String>,
+    #[serde(default)]
+    pub inverse_description: Option<String>,
     pub visibility: Visibility,
     pub origin: String,
     pub destination: String,
     pub cardinality: Cardinality,
     pub attributes: Vec<Attribute>,
+    #[serde(default)]
+    pub timestamps: TimestampFormat,
+    /// Whether every destination must be related to an origin, i.e. whether
+    /// the foreign key of OneToMany and OneToOne relations is NOT NULL.
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// Action on destinations when their origin is deleted, cascade by default.
+    #[serde(default)]
+    pub on_delete: Option<ReferentialAction>,
+    /// Action on destinations when the id of their origin is updated.
+    #[serde(default)]
+    pub on_update: Option<ReferentialAction>,
+    /// Set by the model when an earlier relation is stored alike between the
+    /// same entities, physical names then derive from the relation's basename.
+    #[serde(skip)]
+    pub qualified: bool,
+    #[serde(default)]
+    pub join_key: JoinKey,
+    /// Unique constraints of ManyToMany relations over their attributes
+    /// and the keys of their origin and destination, e.g. `part_id`.
+    #[serde(default)]
+    pub unique_constraints: Vec<UniqueConstraint>,
+    /// Whether links of a ManyToMany relation are ordered per origin,
+    /// through dense zero-based positions.
+    #[serde(default)]
+    pub ordered: bool,
+}
+
+fn default_required() -> bool {
+    true
 }
 
 impl Relation {
     pub fn validate(&self) -> Result<()> {
-        let err = |msg: &str| -> Result<()> {
-            Err(Error::new(&format!(
-                "invalid '{}' relation: {}",
-                self.urn, msg
-            )))
+        Error::first_of(self.errors())
+    }
+
+    /// Every problem of the relation on its own, where `validate` stops at the first.
+    pub fn errors(&self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        let err = |pointer: &str, msg: &str| {
+            Error::with_code(
+                ErrorCode::InvalidRelation,
+                &format!("invalid '{}' relation: {}", self.urn, msg),
+            )
+            .with_urn(&self.urn)
+            .at(pointer)
         };
 
-        urn::is_valid(&self.urn)?;
+        if let Err(e) = urn::is_valid(&self.urn) {
+            errors.push(e.with_urn(&self.urn).at("/urn"));
+        }
+
+        if self
+            .inverse_name
+            .as_deref()
+            .is_some_and(|n| n.trim().is_empty())
+        {
+            errors.push(err("/inverse_name", "inverse name cannot be empty"));
+        }
+
+        if self.inverse_description.is_some() && self.inverse_name.is_none() {
+            errors.push(err(
+                "/inverse_description",
+                "inverse description requires an inverse name",
+            ));
+        }
 
-        if urn::not_basename(&self.urn) != urn::not_basename(&self.origin) {
-            return err(&format!(
-                "origin '{}' does not share the namespace of '{}'",
-                self.urn, self.origin
+        if self.cardinality.is_foreign_key() && !self.attributes.is_empty() {
+            errors.push(err(
+                "/attributes",
+                &format!("{:?} relations cannot have attributes", self.cardinality),
             ));
         }
 
-        if urn::not_basename(&self.urn) != urn::not_basename(&self.destination) {
-            return err(&format!(
-                "destination '{}' does not share the namespace of '{}'",
-                self.urn, self.origin
+        if self.cardinality == Cardinality::ManyToMany && !self.required {
+            errors.push(err("/required", "ManyToMany relations cannot be optional"));
+        }
+
+        if self.is_self_referential() && self.cardinality.is_foreign_key() && self.required {
+            errors.push(err(
+                "/required",
+                "self-referential relations must be optional, roots have no parent",
             ));
         }
 
-        if self.cardinality == Cardinality::OneToMany && !self.attributes.is_empty() {
-            return err(&format!("OneToMany relations cannot have attributes"));
+        let set_null = [self.on_delete, self.on_update].contains(&Some(ReferentialAction::SetNull));
+        if set_null && self.required {
+            let pointer = match self.on_delete {
+                Some(ReferentialAction::SetNull) => "/on_delete",
+                _ => "/on_update",
+            };
+            errors.push(err(
+                pointer,
+                "SetNull is only allowed on optional relations",
+            ));
         }
 
-        let under_id = self.attributes.iter().any(|a| a.id.ends_with("_id"));
-        if under_id {
-            return err("attributes cannot end with '_id'");
+        if self.cardinality.is_foreign_key() && !self.unique_constraints.is_empty() {
+            errors.push(err(
+                "/unique_constraints",
+                &format!(
+                    "{:?} relations cannot have unique constraints",
+                    self.cardinality
+                ),
+            ));
         }
 
-        self.attributes
+        if self.ordered && self.cardinality != Cardinality::ManyToMany {
+            errors.push(err("/ordered", "only ManyToMany relations can be ordered"));
+        }
+
+        let position = self.attributes.iter().position(|a| a.id == POSITION);
+        if let Some(i) = position.filter(|_| self.ordered) {
+            errors.push(err(
+                &format!("/attributes/{}/id", i),
+                &format!(
+                    "attribute id '{}' is reserved in ordered relations",
+                    POSITION
+                ),
+            ));
+        }
+
+        let mut keys = vec![self.origin_key(), self.destination_key()];
+        if self.ordered {
+            keys.push(POSITION.to_owned());
+        }
+        for (i, uc) in self.unique_constraints.iter().enumerate() {
+            let pointer = format!("/unique_constraints/{}", i);
+            if !uc.relations.is_empty() {
+                errors.push(err(
+                    &format!("{}/relations", pointer),
+                    "unique constraints of relations cannot use relations",
+                ));
+            }
+            if uc.attributes.is_empty() {
+                errors.push(err(
+                    &format!("{}/attributes", pointer),
+                    "unique constraints cannot be empty",
+                ));
+            }
+            for (j, id) in uc.attributes.iter().enumerate() {
+                let attribute = self.attributes.iter().find(|attr| attr.id == *id);
+                if attribute.is_some_and(|attr| attr.list) {
+                    errors.push(err(
+                        &format!("{}/attributes/{}", pointer, j),
+                        &format!("list attribute '{}' cannot be unique", id),
+                    ));
+                    continue;
+                }
+                if keys.contains(id) || attribute.is_some() {
+                    continue;
+                }
+                errors.push(
+                    Error::with_code(
+                        ErrorCode::NotFound,
+                        &format!("attribute with id '{}' not found", id),
+                    )
+                    .with_urn(&self.urn)
+                    .at(&format!("{}/attributes/{}", pointer, j)),
+                );
+            }
+        }
+
+        let under_id = self
+            .attributes
             .iter()
-            .map(|attr| attr.validate())
-            .collect::<Result<Vec<()>>>()?;
+            .enumerate()
+            .filter(|(_, a)| a.id.ends_with("_id"));
+        for (i, _) in under_id {
+            errors.push(err(
+                &format!("/attributes/{}/id", i),
+                "attributes cannot end with '_id'",
+            ));
+        }
+
+        for (i, attr) in self.attributes.iter().enumerate() {
+            let checked = attr
+                .validate(self.timestamps)
+                .and_then(|_| attr.validate_expression(&self.attributes));
+            if let Err(e) = checked {
+                errors.push(
+                    e.with_urn(&self.urn)
+                        .with_attribute(&attr.id)
+                        .at(&format!("/attributes/{}", i)),
+                );
+            }
+        }
+
+        errors
+    }
+
+    /// Name of the relation read from the destination, the name itself
+    /// when no inverse name is set.
+    pub fn inverse_name(&self) -> &str {
+        self.inverse_name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Action on destinations when their origin is deleted.
+    pub fn delete_action(&self) -> ReferentialAction {
+        self.on_delete.unwrap_or(ReferentialAction::Cascade)
+    }
+
+    /// Whether the relation relates an entity to itself, e.g. a hierarchy.
+    pub fn is_self_referential(&self) -> bool {
+        self.origin == self.destination
+    }
 
-        Ok(())
+    /// Name of the column referencing the origin, `<singular>_id` or
+    /// `parent_<singular>_id` for self-referential relations. Foreign keys
+    /// of qualified relations are prefixed by the relation's basename.
+    pub fn origin_key(&self) -> String {
+        let key = match self.is_self_referential() {
+            true => format!("parent_{}_id", urn::basename(&self.origin)),
+            false => format!("{}_id", urn::basename(&self.origin)),
+        };
+        match self.qualified && self.cardinality.is_foreign_key() {
+            true => format!("{}_{}", urn::basename(&self.urn), key),
+            false => key,
+        }
+    }
+
+    /// Name of the column referencing the destination in relation tables,
+    /// `<singular>_id` or `child_<singular>_id` for self-referential relations.
+    pub fn destination_key(&self) -> String {
+        match self.is_self_referential() {
+            true => format!("child_{}_id", urn::basename(&self.destination)),
+            false => format!("{}_id", urn::basename(&self.destination)),
+        }
     }
 
     pub fn is_destination(&self, urn: &str, cardinality: Cardinality) -> bool {
         self.cardinality == cardinality && self.destination == urn
     }
-}
 
-impl json_schema::GenerateSchema for Relation {
-    fn json_schema_with_url(&self, _: &str) -> Result<JsonSchema> {
-        let orig_id = format!("{}_id", urn::basename(&self.origin));
-        let dest_id = format!("{}_id", urn::basename(&self.destination));
+    /// Generate the json-schema of the relation given the identity
+    /// strategies of its origin and destination entities.
+    pub fn json_schema_with_ids(
+        &self,
+        origin: Identity,
+        destination: Identity,
+    ) -> Result<JsonSchema> {
+        let orig_id = self.origin_key();
+        let dest_id = self.destination_key();
 
         let mut schema = JsonSchema::new(&self.urn);
-        schema.add_property(&orig_id, Type::new_from_str("integer"), true)?;
-        schema.add_property(&dest_id, Type::new_from_str("integer"), true)?;
+        schema.add_property(&orig_id, origin.as_json_schema_type(), true)?;
+        schema.add_property(&dest_id, destination.as_json_schema_type(), true)?;
+        if self.ordered {
+            let ty = json_schema::Type::new_from_str("integer").with_bounds(Some(0.0), None);
+            schema.add_property(POSITION, ty, true)?;
+        }
         self.attributes
             .iter()
-            .map(|a| schema.add_property(&a.id, a.as_json_schema_type(), a.required))
+            .enumerate()
+            .map(|(i, a)| {
+                a.validate(self.timestamps).map_err(|e| {
+                    e.with_urn(&self.urn)
+                        .with_attribute(&a.id)
+                        .at(&format!("/attributes/{}", i))
+                })?;
+                let ty = a.as_json_schema_type(self.timestamps);
+                schema.add_property(&a.id, ty, a.is_required_input())
+            })
             .collect::<Result<Vec<()>>>()?;
         Ok(schema)
     }
 }
+
+/// Without knowledge of the related entities ids are assumed to be integers,
+/// see `Model::relation_json_schema` otherwise.
+impl json_schema::GenerateSchema for Relation {
+    fn json_schema_with_url(&self, _: &str) -> Result<JsonSchema> {
+        self.validate()?;
+        self.json_schema_with_ids(Identity::Integer, Identity::Integer)
+    }
+}
@@ -1,13 +1,50 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 pub const BASE_ID: &str = "id";
 
+/// Source of the current time for base columns and update triggers.
+#[derive(Clone, Copy)]
+pub enum Clock {
+    /// SQLite `CURRENT_TIMESTAMP`, i.e. `YYYY-MM-DD HH:MM:SS`.
+    CurrentTimestamp,
+    /// RFC 3339 in UTC, i.e. `YYYY-MM-DDTHH:MM:SSZ`.
+    Rfc3339,
+}
+
+impl Clock {
+    /// Expression evaluating to the current time.
+    pub fn as_expr(&self) -> &str {
+        match self {
+            Clock::CurrentTimestamp => "CURRENT_TIMESTAMP",
+            Clock::Rfc3339 => "strftime('%Y-%m-%dT%H:%M:%SZ', 'now')",
+        }
+    }
+
+    /// Expression usable in a column DEFAULT clause.
+    fn as_default(&self) -> String {
+        match self {
+            Clock::CurrentTimestamp => self.as_expr().to_owned(),
+            // SQLite requires parentheses around function calls in DEFAULT
+            Clock::Rfc3339 => format!("({})", self.as_expr()),
+        }
+    }
+}
+
+/// Quote text as an SQL string literal.
+pub fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
 pub enum Type {
     Integer,
     Real,
     Text,
     Boolean,
     Date,
+    Time,
+    Timestamp,
+    Blob,
 }
 
 impl Default for Type {
@@ -23,7 +60,10 @@ impl Type {
             &Type::Real => "REAL",
             &Type::Text => "TEXT",
             &Type::Boolean => "BOOLEAN",
-            &Type::Date => "DATETIME",
+            &Type::Date => "DATE",
+            &Type::Time => "TIME",
+            &Type::Timestamp => "DATETIME",
+            &Type::Blob => "BLOB",
         }
     }
 }
@@ -37,6 +77,10 @@ pub struct Column {
     pub auto_increment: bool,
     pub primary_key: bool,
     pub unique: bool,
+    /// Expressions of CHECK constraints on the column.
+    pub checks: Vec<String>,
+    /// Expression computing the value of the column.
+    pub generated: Option<String>,
 }
 
 impl fmt::Display for Column {
@@ -45,6 +89,9 @@ impl fmt::Display for Column {
 
         parts.push(self.name.clone());
         parts.push(self.ty.as_str().to_owned());
+        if let Some(expr) = &self.generated {
+            parts.push(format!("GENERATED ALWAYS AS ({})", expr));
+        }
         if self.primary_key {
             parts.push("PRIMARY KEY".into());
         }
@@ -65,30 +112,58 @@ impl fmt::Display for Column {
         if self.not_null {
             parts.push("NOT NULL".into());
         }
+        self.checks
+            .iter()
+            .for_each(|check| parts.push(format!("CHECK ({})", check)));
 
         write!(f, "{}", parts.join(" "))
     }
 }
 
+/// Action taken on referencing rows when the referenced row is
+/// deleted or its key updated.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ReferentialAction {
+    Cascade,
+    Restrict,
+    SetNull,
+    NoAction,
+}
+
+impl ReferentialAction {
+    fn as_str(&self) -> &str {
+        match self {
+            ReferentialAction::Cascade => "CASCADE",
+            ReferentialAction::Restrict => "RESTRICT",
+            ReferentialAction::SetNull => "SET NULL",
+            ReferentialAction::NoAction => "NO ACTION",
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct ForeignKey {
     pub key: String,
     pub table_name: String,
     pub table_key: String,
-    pub on_delete_cascade: bool,
+    pub on_delete: Option<ReferentialAction>,
+    pub on_update: Option<ReferentialAction>,
 }
 
 impl fmt::Display for ForeignKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let on_del = match self.on_delete_cascade {
-            true => " ON DELETE CASCADE",
-            false => "",
-        };
         write!(
             f,
-            "FOREIGN KEY ({}) REFERENCES {} ({}){}",
-            self.key, self.table_name, self.table_key, on_del
-        )
+            "FOREIGN KEY ({}) REFERENCES {} ({})",
+            self.key, self.table_name, self.table_key
+        )?;
+        if let Some(action) = self.on_delete {
+            write!(f, " ON DELETE {}", action.as_str())?;
+        }
+        if let Some(action) = self.on_update {
+            write!(f, " ON UPDATE {}", action.as_str())?;
+        }
+        Ok(())
     }
 }
 
@@ -109,16 +184,23 @@ impl fmt::Display for UniqueConstraint {
 pub struct Table {
     pub name: String,
     pub columns: Vec<Column>,
+    /// Columns of a composite primary key, empty when a column is the key.
+    pub primary_key: Vec<String>,
     pub foreign_keys: Vec<ForeignKey>,
     pub unique_constraints: Vec<UniqueConstraint>,
 }
 
 impl fmt::Display for Table {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let primary_key = match self.primary_key.is_empty() {
+            true => None,
+            false => Some(format!("PRIMARY KEY ({})", self.primary_key.join(", "))),
+        };
         let inner = self
             .columns
             .iter()
             .map(|c| c.to_string())
+            .chain(primary_key)
             .chain(self.foreign_keys.iter().map(|c| c.to_string()))
             .chain(self.unique_constraints.iter().map(|c| c.to_string()))
             .collect::<Vec<String>>()
@@ -128,38 +210,109 @@ impl fmt::Display for Table {
 }
 
 impl Table {
-    pub fn new_base(name: &str) -> Table {
+    pub fn new_base(name: &str, id: Column, clock: Clock) -> Table {
         Table {
             name: name.to_owned(),
             columns: vec![
-                Column {
-                    name: BASE_ID.to_owned(),
-                    ty: Type::Integer,
-                    primary_key: true,
-                    auto_increment: true,
-                    ..Default::default()
-                },
+                id,
                 Column {
                     name: "created_at".to_owned(),
-                    ty: Type::Date,
-                    default: Some("CURRENT_TIMESTAMP".to_owned()),
+                    ty: Type::Timestamp,
+                    default: Some(clock.as_default()),
                     not_null: true,
                     ..Default::default()
                 },
                 Column {
                     name: "updated_at".to_owned(),
-                    ty: Type::Date,
-                    default: Some("CURRENT_TIMESTAMP".to_owned()),
+                    ty: Type::Timestamp,
+                    default: Some(clock.as_default()),
                     not_null: true,
                     ..Default::default()
                 },
             ],
+            primary_key: vec![],
             foreign_keys: vec![],
             unique_constraints: vec![],
         }
     }
 }
 
+/// Statements keeping the positions of an ordered relation table dense,
+/// parameterized by `?1` the origin, `?2` the destination and `?3` a position.
+///
+/// Positions are negated while shifted so that the uniqueness of positions
+/// per origin holds after each row update.
+#[derive(Debug, Serialize)]
+pub struct ReorderStatements {
+    /// Move the link of ?1 and ?2 to position ?3, lower than the number of links of ?1.
+    pub move_to: Vec<String>,
+    /// Make room at position ?3 among the links of ?1, before inserting a link there.
+    pub insert: Vec<String>,
+    /// Remove the link of ?1 and ?2 and close the gap it leaves.
+    pub remove: Vec<String>,
+}
+
+impl ReorderStatements {
+    pub fn new(table: &str, origin: &str, destination: &str, position: &str) -> Self {
+        let of_origin = format!("{} = ?1", origin);
+        let old = format!(
+            "(SELECT -1 - {} FROM {} WHERE {} AND {} = ?2)",
+            position, table, of_origin, destination
+        );
+        let update = |set: &str, filter: &str| {
+            format!(
+                "UPDATE {} SET {} = {} WHERE {}{};",
+                table, position, set, of_origin, filter
+            )
+        };
+        let negated = format!(" AND {} < 0", position);
+        let original = format!("-1 - {}", position);
+
+        ReorderStatements {
+            move_to: vec![
+                update(&original, ""),
+                update(
+                    &format!("-2 - {}", position),
+                    &format!(
+                        "{} AND {} > {} AND {} <= ?3",
+                        negated, original, old, original
+                    ),
+                ),
+                update(
+                    &format!("-{}", position),
+                    &format!(
+                        "{} AND {} >= ?3 AND {} < {}",
+                        negated, original, original, old
+                    ),
+                ),
+                format!(
+                    "UPDATE {} SET {} = ?3 WHERE {} AND {} = ?2;",
+                    table, position, of_origin, destination
+                ),
+                update(&original, &negated),
+            ],
+            insert: vec![
+                update(&original, &format!(" AND {} >= ?3", position)),
+                update(&format!("-{}", position), &negated),
+            ],
+            remove: vec![
+                update(
+                    &original,
+                    &format!(
+                        " AND {} > (SELECT {} FROM {} WHERE {} AND {} = ?2)",
+                        position, position, table, of_origin, destination
+                    ),
+                ),
+                format!(
+                    "DELETE FROM {} WHERE {} AND {} = ?2;",
+                    table, of_origin, destination
+                ),
+                update(&format!("-2 - {}", position), &negated),
+            ],
+        }
+    }
+}
+
 pub struct Trigger {
     pub table_name: String,
     pub column: String,
@@ -170,18 +323,22 @@ pub struct Trigger {
 impl fmt::Display for Trigger {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f,
-            "CREATE TRIGGER {}_{} AFTER UPDATE ON {} WHEN old.{} < {} BEGIN UPDATE {} SET {} = {} WHERE {} = old.{}; END;",
-            self.table_name, self.column, self.table_name, self.column, self.value, self.table_name, self.column, self.value, self.id, self.id)
+            "CREATE TRIGGER {} AFTER UPDATE ON {} WHEN old.{} < {} BEGIN UPDATE {} SET {} = {} WHERE {} = old.{}; END;",
+            self.name(), self.table_name, self.column, self.value, self.table_name, self.column, self.value, self.id, self.id)
     }
 }
 
 impl Trigger {
-    pub fn new_update_trigger(name: &str) -> Trigger {
+    pub fn name(&self) -> String {
+        format!("{}_{}", self.table_name, self.column)
+    }
+
+    pub fn new_update_trigger(name: &str, clock: Clock) -> Trigger {
         Trigger {
             table_name: name.to_owned(),
             column: "updated_at".to_owned(),
             id: BASE_ID.to_owned(),
-            value: "CURRENT_TIMESTAMP".to_owned(),
+            value: clock.as_expr().to_owned(),
         }
     }
 }
@@ -208,6 +365,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sql_base_table_rfc3339() {
+        assert_eq!(
+            Table::new_base(
+                "parts",
+                Column {
+                    name: BASE_ID.to_owned(),
+                    ty: Type::Integer,
+                    primary_key: true,
+                    auto_increment: true,
+                    ..Default::default()
+                },
+                Clock::Rfc3339
+            )
+            .to_string(),
+            "CREATE TABLE parts(id INTEGER PRIMARY KEY AUTOINCREMENT, created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')) NOT NULL, updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')) NOT NULL);"
+        );
+        assert_eq!(
+            Trigger::new_update_trigger("parts", Clock::Rfc3339).to_string(),
+            "CREATE TRIGGER parts_updated_at AFTER UPDATE ON parts WHEN old.updated_at < strftime('%Y-%m-%dT%H:%M:%SZ', 'now') BEGIN UPDATE parts SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = old.id; END;"
+        );
+    }
+
     #[test]
     fn sql_print_column() {
         let column = Column {
@@ -218,6 +398,8 @@ mod tests {
             auto_increment: true,
             primary_key: true,
             unique: false,
+            checks: vec![],
+            generated: None,
         };
 
         assert_eq!(
@@ -226,19 +408,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sql_print_column_checks() {
+        let column = Column {
+            name: "status".into(),
+            ty: Type::Text,
+            not_null: true,
+            checks: vec![format!("status IN ({}, {})", quote("new"), quote("o'clock"))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            column.to_string(),
+            "status TEXT NOT NULL CHECK (status IN ('new', 'o''clock'))".to_string()
+        );
+    }
+
+    #[test]
+    fn sql_reorder_statements() {
+        let statements = ReorderStatements::new("kit_parts", "kit_id", "part_id", "position");
+        assert_eq!(
+            statements.move_to,
+            vec![
+                "UPDATE kit_parts SET position = -1 - position WHERE kit_id = ?1;",
+                "UPDATE kit_parts SET position = -2 - position WHERE kit_id = ?1 AND position < 0 AND -1 - position > (SELECT -1 - position FROM kit_parts WHERE kit_id = ?1 AND part_id = ?2) AND -1 - position <= ?3;",
+                "UPDATE kit_parts SET position = -position WHERE kit_id = ?1 AND position < 0 AND -1 - position >= ?3 AND -1 - position < (SELECT -1 - position FROM kit_parts WHERE kit_id = ?1 AND part_id = ?2);",
+                "UPDATE kit_parts SET position = ?3 WHERE kit_id = ?1 AND part_id = ?2;",
+                "UPDATE kit_parts SET position = -1 - position WHERE kit_id = ?1 AND position < 0;",
+            ]
+        );
+        assert_eq!(
+            statements.insert,
+            vec![
+                "UPDATE kit_parts SET position = -1 - position WHERE kit_id = ?1 AND position >= ?3;",
+                "UPDATE kit_parts SET position = -position WHERE kit_id = ?1 AND position < 0;",
+            ]
+        );
+        assert_eq!(
+            statements.remove,
+            vec![
+                "UPDATE kit_parts SET position = -1 - position WHERE kit_id = ?1 AND position > (SELECT position FROM kit_parts WHERE kit_id = ?1 AND part_id = ?2);",
+                "DELETE FROM kit_parts WHERE kit_id = ?1 AND part_id = ?2;",
+                "UPDATE kit_parts SET position = -2 - position WHERE kit_id = ?1 AND position < 0;",
+            ]
+        );
+    }
+
     #[test]
     fn sql_print_foreignkey() {
         let foreign_key = ForeignKey {
             key: "category_id".into(),
             table_name: "categories".into(),
             table_key: BASE_ID.into(),
-            on_delete_cascade: true,
+            on_delete: Some(ReferentialAction::Cascade),
+            on_update: None,
         };
 
         assert_eq!(
             foreign_key.to_string(),
             "FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE".to_string()
         );
+
+        let foreign_key = ForeignKey {
+            on_delete: Some(ReferentialAction::SetNull),
+            on_update: Some(ReferentialAction::Restrict),
+            ..foreign_key
+        };
+        assert_eq!(
+            foreign_key.to_string(),
+            "FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL ON UPDATE RESTRICT".to_string()
+        );
     }
 
     #[test]
@@ -255,14 +494,14 @@ mod tests {
                 },
                 Column {
                     name: "created_at".to_owned(),
-                    ty: Type::Date,
+                    ty: Type::Timestamp,
                     default: Some("CURRENT_TIMESTAMP".to_owned()),
                     not_null: true,
                     ..Default::default()
                 },
                 Column {
                     name: "updated_at".to_owned(),
-                    ty: Type::Date,
+                    ty: Type::Timestamp,
                     default: Some("CURRENT_TIMESTAMP".to_owned()),
                     not_null: true,
                     ..Default::default()
@@ -280,6 +519,7 @@ mod tests {
                     ..Default::default()
                 },
             ],
+            primary_key: Vec::new(),
             foreign_keys: Vec::new(),
             unique_constraints: Vec::new(),
         };
@@ -1,11 +1,14 @@
-use crate::error::{Error, Result};
+use crate::error::{Error, ErrorCode, Result};
 
 pub fn is_valid(urn: &str) -> Result<()> {
     urn.chars()
         .map(|c| match c {
             ':' | '_' => Ok(()),
             'a'..='z' | '0'..='9' => Ok(()),
-            a => Err(Error::new(&format!("invalid char: {}", a))),
+            a => Err(Error::with_code(
+                ErrorCode::InvalidUrn,
+                &format!("invalid char: {}", a),
+            )),
         })
         .collect::<Result<()>>()
 }
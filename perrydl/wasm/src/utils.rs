@@ -7,3 +7,192 @@ pub fn is_snake_case(text: &str) -> bool {
         })
         .is_none()
 }
+
+/// Whether text matches a shape where 'd' stands for any ASCII digit.
+fn has_shape(text: &str, shape: &str) -> bool {
+    text.len() == shape.len()
+        && text.chars().zip(shape.chars()).all(|(c, s)| match s {
+            'd' => c.is_ascii_digit(),
+            _ => c == s,
+        })
+}
+
+/// Whether text is a full-date, e.g. `2020-06-23`.
+pub fn is_date(text: &str) -> bool {
+    has_shape(text, "dddd-dd-dd")
+}
+
+/// Whether text is a partial-time, e.g. `11:59:45` or `11:59:45.120`.
+pub fn is_time(text: &str) -> bool {
+    let mut parts = text.splitn(2, '.');
+    let whole = parts.next().unwrap_or_default();
+    let fraction_ok = match parts.next() {
+        Some(fraction) => !fraction.is_empty() && fraction.chars().all(|c| c.is_ascii_digit()),
+        None => true,
+    };
+    has_shape(whole, "dd:dd:dd") && fraction_ok
+}
+
+/// Whether text is an RFC 3339 date-time, e.g. `2020-06-23T11:59:45Z`.
+pub fn is_date_time(text: &str) -> bool {
+    let (date, rest) = match (text.get(..10), text.get(10..)) {
+        (Some(date), Some(rest)) => (date, rest),
+        _ => return false,
+    };
+    let time = match rest.strip_prefix('T').or_else(|| rest.strip_prefix('t')) {
+        Some(time) => time,
+        None => return false,
+    };
+    let time = match time.strip_suffix('Z').or_else(|| time.strip_suffix('z')) {
+        Some(time) => time,
+        None => {
+            let split = time.len().saturating_sub(6);
+            match (time.get(..split), time.get(split..)) {
+                (Some(time), Some(offset))
+                    if has_shape(offset, "+dd:dd") || has_shape(offset, "-dd:dd") =>
+                {
+                    time
+                }
+                _ => return false,
+            }
+        }
+    };
+    is_date(date) && is_time(time)
+}
+
+/// Regular expression matching decimals with at most `precision` digits,
+/// `scale` of them after the decimal point, none if the scale is larger.
+pub fn decimal_pattern(precision: u32, scale: u32) -> Option<String> {
+    let integer = match precision.checked_sub(scale)? {
+        0 => "0".to_owned(),
+        digits => format!("[0-9]{{1,{}}}", digits),
+    };
+    let fraction = match scale {
+        0 => "".to_owned(),
+        digits => format!("(\\.[0-9]{{1,{}}})?", digits),
+    };
+    Some(format!("^-?{}{}$", integer, fraction))
+}
+
+/// SQL expression holding when a text column matches
+/// `decimal_pattern(precision, scale)`, spelled with GLOB since SQLite
+/// has no REGEXP. None if the scale is larger than the precision.
+pub fn decimal_check(column: &str, precision: u32, scale: u32) -> Option<String> {
+    let integer = precision.checked_sub(scale)?;
+    let dot = format!("instr({} || '.', '.')", column);
+    let mut parts = vec![
+        format!("({} GLOB '[0-9]*' OR {} GLOB '-[0-9]*')", column, column),
+        format!("substr({}, 2) NOT GLOB '*[^0-9.]*'", column),
+        format!("{} NOT GLOB '*.*.*'", column),
+        format!("substr({}, -1) GLOB '[0-9]'", column),
+        format!("{} - ({} GLOB '-*') <= {}", dot, column, integer.max(1) + 1),
+        format!("length({}) - {} <= {}", column, dot, scale),
+    ];
+    if integer == 0 {
+        parts.push(format!("({} GLOB '0*' OR {} GLOB '-0*')", column, column));
+    }
+    Some(parts.join(" AND "))
+}
+
+/// Whether text is a decimal matching `decimal_pattern(precision, scale)`.
+pub fn is_decimal(text: &str, precision: u32, scale: u32) -> bool {
+    let digits = |part: &str, max: u32| {
+        !part.is_empty() && part.len() as u32 <= max && part.chars().all(|c| c.is_ascii_digit())
+    };
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    let mut parts = unsigned.splitn(2, '.');
+    let integer = parts.next().unwrap_or_default();
+    let integer_ok = match precision.checked_sub(scale) {
+        Some(0) => integer == "0",
+        Some(max) => digits(integer, max),
+        None => false,
+    };
+    let fraction_ok = match parts.next() {
+        Some(fraction) => digits(fraction, scale),
+        None => true,
+    };
+    integer_ok && fraction_ok
+}
+
+/// Whether text is a `type/subtype` media type without parameters,
+/// e.g. `application/pdf` or `image/*`.
+pub fn is_media_type(text: &str) -> bool {
+    let token = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+    };
+    match text.split_once('/') {
+        Some((ty, "*")) => token(ty),
+        Some((ty, subtype)) => token(ty) && token(subtype),
+        None => false,
+    }
+}
+
+/// Number of base64 characters encoding `size` bytes, padding included.
+pub fn base64_length(size: u64) -> u64 {
+    (size / 3 + (size % 3).min(1)) * 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temporal_shapes() {
+        assert!(is_date("2020-06-23"));
+        assert!(!is_date("2020-6-23"));
+        assert!(is_time("11:59:45"));
+        assert!(is_time("11:59:45.120"));
+        assert!(!is_time("11:59"));
+        assert!(!is_time("11:59:45."));
+        assert!(is_date_time("2020-06-23T11:59:45Z"));
+        assert!(is_date_time("2020-06-23T11:59:45.5+01:00"));
+        assert!(!is_date_time("2020-06-23 11:59:45"));
+        assert!(!is_date_time("2020-06-23T11:59:45"));
+        assert!(!is_date_time("2020-06-23"));
+    }
+
+    #[test]
+    fn decimals() {
+        let pattern = |precision, scale| decimal_pattern(precision, scale).unwrap();
+        assert_eq!(pattern(10, 2), "^-?[0-9]{1,8}(\\.[0-9]{1,2})?$");
+        assert_eq!(pattern(4, 0), "^-?[0-9]{1,4}$");
+        assert_eq!(pattern(3, 3), "^-?0(\\.[0-9]{1,3})?$");
+        assert_eq!(decimal_pattern(2, 3), None);
+        assert_eq!(
+            decimal_check("ratio", 3, 3).unwrap(),
+            "(ratio GLOB '[0-9]*' OR ratio GLOB '-[0-9]*') \
+             AND substr(ratio, 2) NOT GLOB '*[^0-9.]*' AND ratio NOT GLOB '*.*.*' \
+             AND substr(ratio, -1) GLOB '[0-9]' \
+             AND instr(ratio || '.', '.') - (ratio GLOB '-*') <= 2 \
+             AND length(ratio) - instr(ratio || '.', '.') <= 3 \
+             AND (ratio GLOB '0*' OR ratio GLOB '-0*')"
+        );
+        assert_eq!(decimal_check("price", 2, 3), None);
+        assert!(is_decimal("12345678.99", 10, 2));
+        assert!(is_decimal("-0.5", 10, 2));
+        assert!(!is_decimal("123456789", 10, 2));
+        assert!(!is_decimal("1.999", 10, 2));
+        assert!(!is_decimal("1.", 10, 2));
+        assert!(!is_decimal("1.5", 4, 0));
+        assert!(is_decimal("0.125", 3, 3));
+        assert!(!is_decimal("1.125", 3, 3));
+        assert!(!is_decimal("0.5", 2, 3));
+    }
+
+    #[test]
+    fn attachments() {
+        assert!(is_media_type("application/pdf"));
+        assert!(is_media_type("image/svg+xml"));
+        assert!(is_media_type("image/*"));
+        assert!(!is_media_type("image"));
+        assert!(!is_media_type("image/"));
+        assert!(!is_media_type("text/plain; charset=utf-8"));
+        assert_eq!(base64_length(0), 0);
+        assert_eq!(base64_length(1), 4);
+        assert_eq!(base64_length(3), 4);
+        assert_eq!(base64_length(1024), 1368);
+    }
+}
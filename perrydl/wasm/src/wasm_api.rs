@@ -1,7 +1,8 @@
 use crate::entity::Entity;
-use crate::error::{Error, Result};
+use crate::error::{Diagnostic, Error, Result};
 use crate::json_schema::GenerateSchema;
-use crate::model::Model as LibModel;
+use crate::lint;
+use crate::model::{Model as LibModel, SerdeModel};
 use crate::relation::Relation;
 use crate::sql;
 use inflector::Inflector;
@@ -14,9 +15,13 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 use wasm_bindgen::prelude::*;
 
+/// Errors reach JavaScript as `{ code, message, urn?, attribute?, pointer }`
+/// objects, the pointer locating the offending value in the submitted spec.
+/// Callers read `message` rather than `toString()`, which these plain objects
+/// do not override.
 impl From<Error> for JsValue {
     fn from(err: Error) -> JsValue {
-        JsValue::from_str(&err.to_string())
+        JsValue::from_serde(&err).unwrap_or_else(|_| JsValue::from_str(&err.to_string()))
     }
 }
 
@@ -40,7 +45,8 @@ pub fn generate_entity_schema(payload: JsValue) -> JsResult<JsValue> {
     Ok(gen_schema::<Entity>(payload)?)
 }
 
-/// Generate json-schema for an Entity.
+/// Generate json-schema for a Relation, assuming integer ids.
+/// See `Model.relationSchema` for models using other identities.
 /// Exported to JS.
 #[wasm_bindgen(catch)]
 pub fn generate_relation_schema(payload: JsValue) -> JsResult<JsValue> {
@@ -67,19 +73,64 @@ pub struct Model {
     inner: LibModel,
 }
 
-fn model_from_object(payload: JsValue) -> Result<Model> {
-    let inner: LibModel = LibModel::new(payload.into_serde()?)?;
+fn model_from_object(payload: JsValue, owners: JsValue) -> Result<Model> {
+    let owners = owners_from_object(owners)?;
+    let inner: LibModel = LibModel::importing(payload.into_serde()?, &owners)?;
     Ok(Model { inner })
 }
 
+/// Models owning the entities a model imports, given as an array of specs
+/// each of which may import from those before it.
+fn owners_from_object(owners: JsValue) -> Result<Vec<LibModel>> {
+    if owners.is_undefined() || owners.is_null() {
+        return Ok(vec![]);
+    }
+    let specs: Vec<SerdeModel> = owners.into_serde()?;
+    let mut models = Vec::new();
+    for spec in specs {
+        let model = LibModel::importing(spec, &models)?;
+        models.push(model);
+    }
+    Ok(models)
+}
+
 #[wasm_bindgen]
 impl Model {
+    /// Build a model, resolving its imports against the specs of the
+    /// models owning them, if any. The tables of imported entities are
+    /// left to those models.
     #[wasm_bindgen(catch)]
-    pub fn from_object(payload: JsValue) -> JsResult<Model> {
-        let model = model_from_object(payload)?;
+    pub fn from_object(payload: JsValue, owners: JsValue) -> JsResult<Model> {
+        let model = model_from_object(payload, owners)?;
         Ok(model)
     }
 
+    /// Validate a model without building it, listing every diagnostic
+    /// found, as structured errors with a severity. `from_object` succeeds
+    /// when no diagnostic has the `Error` severity, warnings aside.
+    #[wasm_bindgen(js_name = "validateObject")]
+    pub fn validate_object(payload: JsValue, owners: JsValue) -> JsValue {
+        let diagnostics = match (payload.into_serde(), owners_from_object(owners)) {
+            (Ok(serde_model), Ok(owners)) => LibModel::diagnose_importing(serde_model, &owners),
+            (Err(e), _) => vec![Diagnostic::error(Error::from(e))],
+            (_, Err(e)) => vec![Diagnostic::error(e)],
+        };
+        JsValue::from_serde(&diagnostics).unwrap()
+    }
+
+    /// Advisory diagnostics of the model, structured like those of
+    /// `validateObject`. Rules are warnings unless set otherwise in config,
+    /// e.g. `{ rules: { MissingDescription: "Off", Plural: "Error" } }`.
+    #[wasm_bindgen(catch)]
+    pub fn lint(&self, config: JsValue) -> JsResult<JsValue> {
+        let config: lint::Config = match config.is_undefined() || config.is_null() {
+            true => lint::Config::default(),
+            false => config.into_serde().map_err(Error::from)?,
+        };
+        let diagnostics = self.inner.lint(&config);
+        Ok(JsValue::from_serde(&diagnostics).map_err(Error::from)?)
+    }
+
     pub fn to_sql(&self) -> String {
         use sql::GenerateSql;
         self.inner.generate_sql().join("\n")
@@ -97,11 +148,47 @@ impl Model {
         Ok(res.to_string())
     }
 
+    /// Generate json-schema for one of the model's entities,
+    /// including the foreign keys of its relations.
+    #[wasm_bindgen(catch, js_name = "entitySchema")]
+    pub fn entity_schema(&self, urn: &str) -> JsResult<JsValue> {
+        let schema = self.inner.entity_json_schema(urn)?;
+        Ok(JsValue::from_serde(&schema).map_err(Error::from)?)
+    }
+
+    /// Generate json-schema for one of the model's relations.
+    #[wasm_bindgen(catch, js_name = "relationSchema")]
+    pub fn relation_schema(&self, urn: &str) -> JsResult<JsValue> {
+        let schema = self.inner.relation_json_schema(urn)?;
+        Ok(JsValue::from_serde(&schema).map_err(Error::from)?)
+    }
+
     #[wasm_bindgen(js_name = "sqlIsRelationTable")]
     pub fn sql_is_relation_table(&self, name: &str) -> bool {
         self.inner.is_relation_table(name)
     }
 
+    /// Relations of an entity as navigated from it, named after their
+    /// inverse name when the entity is their destination.
+    #[wasm_bindgen(catch)]
+    pub fn navigation(&self, urn: &str) -> JsResult<JsValue> {
+        let navigation = self.inner.navigation(urn)?;
+        Ok(JsValue::from_serde(&navigation).map_err(Error::from)?)
+    }
+
+    /// Tables and columns storing each relation.
+    #[wasm_bindgen(js_name = "relationMappings")]
+    pub fn relation_mappings(&self) -> JsValue {
+        JsValue::from_serde(&self.inner.relation_mappings()).unwrap()
+    }
+
+    /// Statements keeping positions of an ordered relation dense.
+    #[wasm_bindgen(catch, js_name = "reorderStatements")]
+    pub fn reorder_statements(&self, urn: &str) -> JsResult<JsValue> {
+        let statements = self.inner.reorder_sql(urn)?;
+        Ok(JsValue::from_serde(&statements).map_err(Error::from)?)
+    }
+
     #[wasm_bindgen(js_name = "sqlTableNames")]
     pub fn sql_table_names(&self) -> JsValue {
         JsValue::from_serde(&self.inner.table_names()).unwrap()